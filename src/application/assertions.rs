@@ -0,0 +1,455 @@
+use crate::domain::entities::Response;
+use crate::domain::json_path::JsonPath;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Checks applied to a response after it has been received
+///
+/// Every enabled check is evaluated and all failures are reported together.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseAssertions {
+    /// Require the response body to be valid JSON
+    pub valid_json: bool,
+    /// Upper bound on the total request time
+    pub max_time: Option<Duration>,
+    /// Cookies the response must set
+    pub cookies: Vec<CookieExpectation>,
+    /// Treat a 3xx response as a failure
+    pub no_redirect: bool,
+}
+
+impl ResponseAssertions {
+    /// Runs every enabled check against the response
+    ///
+    /// # Returns
+    /// * `Ok(())` - All checks passed
+    /// * `Err(anyhow::Error)` - Lists every failed check
+    pub fn check(&self, response: &Response) -> Result<()> {
+        let mut failures: Vec<String> = [
+            self.redirect_failure(response),
+            self.json_failure(response),
+            self.time_failure(response),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        failures.extend(
+            self.cookies
+                .iter()
+                .filter_map(|cookie| cookie.failure(response)),
+        );
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Assertion failed: {}", failures.join("; ")))
+        }
+    }
+
+    fn redirect_failure(&self, response: &Response) -> Option<String> {
+        if !self.no_redirect || !response.status.is_redirection() {
+            return None;
+        }
+        Some(match response.header("location") {
+            Some(location) => format!("Unexpected redirect: {} to {}", response.status, location),
+            None => format!("Unexpected redirect: {}", response.status),
+        })
+    }
+
+    fn time_failure(&self, response: &Response) -> Option<String> {
+        let limit = self.max_time?;
+        let total = response.timing.total;
+        (total > limit).then(|| {
+            format!(
+                "Request took {}ms, exceeding the {}ms limit",
+                total.as_millis(),
+                limit.as_millis()
+            )
+        })
+    }
+
+    fn json_failure(&self, response: &Response) -> Option<String> {
+        if !self.valid_json {
+            return None;
+        }
+        serde_json::from_str::<Value>(&response.text())
+            .err()
+            .map(|e| format!("Response body is not valid JSON: {}", e))
+    }
+}
+
+/// A cookie the response must set, e.g. `session` or `session=abc123`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieExpectation {
+    pub name: String,
+    /// Expected value; any value is accepted when absent
+    pub value: Option<String>,
+}
+
+impl CookieExpectation {
+    // Later Set-Cookie headers for the same name override earlier ones, as in a browser
+    fn failure(&self, response: &Response) -> Option<String> {
+        let value = response
+            .headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("set-cookie"))
+            .filter_map(|(_, header)| parse_set_cookie(header))
+            .filter(|(name, _)| *name == self.name)
+            .map(|(_, value)| value)
+            .next_back();
+
+        match (value, &self.value) {
+            (None, _) => Some(format!("Response sets no '{}' cookie", self.name)),
+            (Some(actual), Some(expected)) if actual != expected => Some(format!(
+                "Cookie '{}' is '{}', expected '{}'",
+                self.name, actual, expected
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for CookieExpectation {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let (name, value) = match raw.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().to_string())),
+            None => (raw.trim(), None),
+        };
+        if name.is_empty() {
+            return Err(anyhow!(
+                "Invalid cookie expectation '{}': expected NAME or NAME=VALUE",
+                raw
+            ));
+        }
+        Ok(CookieExpectation {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+// Splits `name=value; Path=/; HttpOnly` into its name and value, dropping the attributes
+fn parse_set_cookie(header: &str) -> Option<(&str, &str)> {
+    let pair = header.split(';').next()?;
+    let (name, value) = pair.split_once('=')?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((name.trim(), value))
+}
+
+/// One or more status codes or classes, e.g. `200`, `2xx` or `200,3xx,404`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusPattern(Vec<StatusMatch>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusMatch {
+    Code(u16),
+    /// The hundreds digit, e.g. 2 for `2xx`
+    Class(u16),
+}
+
+impl StatusPattern {
+    pub fn matches(&self, status: u16) -> bool {
+        self.0.iter().any(|pattern| match pattern {
+            StatusMatch::Code(code) => *code == status,
+            StatusMatch::Class(class) => status / 100 == *class,
+        })
+    }
+}
+
+impl FromStr for StatusPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(|part| {
+                let part = part.trim().to_lowercase();
+                let invalid = || {
+                    anyhow!(
+                        "Invalid status '{}'. Use a code like 200 or a class like 2xx",
+                        part
+                    )
+                };
+                match part.strip_suffix("xx") {
+                    Some(class) if class.len() == 1 => match class.parse::<u16>() {
+                        Ok(class @ 1..=5) => Ok(StatusMatch::Class(class)),
+                        _ => Err(invalid()),
+                    },
+                    Some(_) => Err(invalid()),
+                    None => match part.parse::<u16>() {
+                        Ok(code @ 100..=599) => Ok(StatusMatch::Code(code)),
+                        _ => Err(invalid()),
+                    },
+                }
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(StatusPattern)
+    }
+}
+
+/// The part of a response a condition looks at
+#[derive(Debug, Clone)]
+enum Subject {
+    Status,
+    Body,
+    Json(JsonPath),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+}
+
+/// A single expectation about a response, e.g. `status == 200` or `$.state == "done"`
+///
+/// Written as `<subject> <operator> <value>`, where the subject is `status`, `body`
+/// or a JSONPath, the operator is `==`, `!=` or `contains`, and the value is a JSON
+/// literal or bare text.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    source: String,
+    subject: Subject,
+    operator: Operator,
+    expected: Value,
+}
+
+impl Condition {
+    /// Evaluates the condition; a JSONPath that matches nothing never holds
+    pub fn is_met(&self, response: &Response) -> bool {
+        let actual = match &self.subject {
+            Subject::Status => Value::from(response.status.as_u16()),
+            Subject::Body => Value::String(response.text().into_owned()),
+            Subject::Json(path) => {
+                let Ok(json) = serde_json::from_str::<Value>(&response.text()) else {
+                    return false;
+                };
+                match path.select(&json) {
+                    Some(value) => value.clone(),
+                    None => return false,
+                }
+            }
+        };
+
+        match self.operator {
+            Operator::Equals => actual == self.expected,
+            Operator::NotEquals => actual != self.expected,
+            Operator::Contains => match (&actual, &self.expected) {
+                (Value::Array(items), expected) => items.contains(expected),
+                (Value::String(text), Value::String(needle)) => text.contains(needle.as_str()),
+                (Value::String(text), expected) => text.contains(&expected.to_string()),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow!(
+                "Invalid condition '{}'. Use '<status|body|$.path> <==|!=|contains> <value>'",
+                s
+            )
+        };
+        let (subject, rest) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid)?;
+        let (operator, expected) = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid)?;
+
+        let subject = match subject {
+            "status" => Subject::Status,
+            "body" => Subject::Body,
+            path if path.starts_with('$') => Subject::Json(JsonPath::new(path)?),
+            _ => return Err(invalid()),
+        };
+        let operator = match operator {
+            "==" => Operator::Equals,
+            "!=" => Operator::NotEquals,
+            "contains" => Operator::Contains,
+            _ => return Err(invalid()),
+        };
+        let expected = expected.trim();
+        let expected =
+            serde_json::from_str(expected).unwrap_or_else(|_| Value::String(expected.to_string()));
+
+        Ok(Condition {
+            source: s.trim().to_string(),
+            subject,
+            operator,
+            expected,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+        let mut response = MockHttpClient::reply(status, body);
+        response.headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        response
+    }
+
+    #[test]
+    fn valid_json_checks_the_body() {
+        let assertions = ResponseAssertions {
+            valid_json: true,
+            ..Default::default()
+        };
+        assert!(
+            assertions
+                .check(&response(200, &[], r#"{"ok": true}"#))
+                .is_ok()
+        );
+        let error = assertions.check(&response(200, &[], "<html>")).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Assertion failed: Response body is not valid JSON")
+        );
+        assert!(
+            ResponseAssertions::default()
+                .check(&response(200, &[], "<html>"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn max_time_fails_slow_responses() {
+        let assertions = ResponseAssertions {
+            max_time: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let mut slow = response(200, &[], "");
+        slow.timing.total = Duration::from_millis(150);
+        assert_eq!(
+            assertions.check(&slow).unwrap_err().to_string(),
+            "Assertion failed: Request took 150ms, exceeding the 100ms limit"
+        );
+        slow.timing.total = Duration::from_millis(100);
+        assert!(assertions.check(&slow).is_ok());
+    }
+
+    #[test]
+    fn cookie_expectations_parse_name_and_value() {
+        let any: CookieExpectation = "session".parse().unwrap();
+        assert_eq!(any.value, None);
+        let exact: CookieExpectation = " session = abc ".parse().unwrap();
+        assert_eq!(
+            (exact.name.as_str(), exact.value.as_deref()),
+            ("session", Some("abc"))
+        );
+        assert!("=abc".parse::<CookieExpectation>().is_err());
+    }
+
+    #[test]
+    fn cookie_expectations_use_the_last_set_cookie() {
+        let assertions = ResponseAssertions {
+            cookies: vec!["session=new".parse().unwrap(), "theme".parse().unwrap()],
+            ..Default::default()
+        };
+        let headers = [
+            ("Set-Cookie", "session=old; Path=/"),
+            ("set-cookie", "session=\"new\"; HttpOnly"),
+        ];
+        assert_eq!(
+            assertions
+                .check(&response(200, &headers, ""))
+                .unwrap_err()
+                .to_string(),
+            "Assertion failed: Response sets no 'theme' cookie"
+        );
+
+        let stale = [("Set-Cookie", "session=old"), ("Set-Cookie", "theme=dark")];
+        assert_eq!(
+            assertions
+                .check(&response(200, &stale, ""))
+                .unwrap_err()
+                .to_string(),
+            "Assertion failed: Cookie 'session' is 'old', expected 'new'"
+        );
+    }
+
+    #[test]
+    fn no_redirect_fails_3xx_responses() {
+        let assertions = ResponseAssertions {
+            no_redirect: true,
+            valid_json: true,
+            ..Default::default()
+        };
+        let redirect = response(302, &[("Location", "/login")], "");
+        assert_eq!(
+            assertions.check(&redirect).unwrap_err().to_string(),
+            "Assertion failed: Unexpected redirect: 302 Found to /login; \
+             Response body is not valid JSON: EOF while parsing a value at line 1 column 0"
+        );
+        assert!(assertions.check(&response(200, &[], "{}")).is_ok());
+    }
+
+    #[test]
+    fn conditions_check_status_body_and_json() {
+        let done = response(200, &[], r#"{"state": "done", "tags": ["a", "b"], "n": 3}"#);
+        let holds = |condition: &str| condition.parse::<Condition>().unwrap().is_met(&done);
+        assert!(holds("status == 200"));
+        assert!(holds("status != 202"));
+        assert!(holds("body contains done"));
+        assert!(holds(r#"$.state == "done""#));
+        assert!(holds("$.state == done"));
+        assert!(holds(r#"$.tags contains "b""#));
+        assert!(holds("$.n == 3"));
+        assert!(!holds("$.missing != 1"));
+    }
+
+    #[test]
+    fn invalid_conditions_are_errors() {
+        for condition in ["status", "status ~= 200", "headers == x", "$. == 1"] {
+            assert!(condition.parse::<Condition>().is_err(), "{:?}", condition);
+        }
+        let condition: Condition = "  status ==  200 ".parse().unwrap();
+        assert_eq!(condition.to_string(), "status ==  200");
+    }
+
+    #[test]
+    fn status_patterns_match_codes_and_classes() {
+        let pattern: StatusPattern = "200, 3XX,404".parse().unwrap();
+        assert!(pattern.matches(200));
+        assert!(pattern.matches(301));
+        assert!(pattern.matches(404));
+        assert!(!pattern.matches(201));
+        assert!(!pattern.matches(500));
+    }
+
+    #[test]
+    fn invalid_status_patterns_are_errors() {
+        for pattern in ["", "abc", "99", "600", "6xx", "20x", "2xxx"] {
+            assert!(pattern.parse::<StatusPattern>().is_err(), "{:?}", pattern);
+        }
+    }
+}
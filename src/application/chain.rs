@@ -0,0 +1,251 @@
+use crate::application::services::HttpRequestService;
+use crate::domain::entities::{Body, Method, Request, Response};
+use crate::domain::json_path::JsonPath;
+use crate::domain::template::Template;
+use crate::domain::value_objects::{JsonBody, Url};
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Where a captured value is read from in a response
+#[derive(Debug, Clone)]
+enum Source {
+    Json(JsonPath),
+    Header(String),
+}
+
+/// Stores part of a response under a variable name for later steps
+#[derive(Debug, Clone)]
+pub struct Capture {
+    name: String,
+    source: Source,
+}
+
+impl Capture {
+    // JSON strings are captured without their quotes; other values as JSON text
+    fn extract(&self, response: &Response) -> Result<String> {
+        let missing = || anyhow!("Capture '{}' found nothing in the response", self.name);
+
+        match &self.source {
+            Source::Header(header) => response
+                .header(header)
+                .map(str::to_string)
+                .ok_or_else(missing),
+            Source::Json(path) => {
+                let json = serde_json::from_str::<Value>(&response.text())
+                    .map_err(|e| anyhow!("Capture '{}' needs a JSON response: {}", self.name, e))?;
+                match path.select(&json).ok_or_else(missing)? {
+                    Value::String(text) => Ok(text.clone()),
+                    value => Ok(value.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// One request in a chain, with `{{name}}` placeholders filled from captured variables
+#[derive(Debug, Clone)]
+pub struct ChainStep {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    captures: Vec<Capture>,
+}
+
+impl ChainStep {
+    pub fn new(method: Method, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: None,
+            captures: Vec::new(),
+        }
+    }
+
+    /// Adds a header; the value may contain placeholders
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the JSON body; it is validated after placeholders are filled
+    pub fn body(mut self, json: &str) -> Self {
+        self.body = Some(json.to_string());
+        self
+    }
+
+    /// Captures the value at a JSONPath in the response body
+    pub fn capture_json(mut self, name: &str, path: &str) -> Result<Self> {
+        self.captures.push(Capture {
+            name: name.to_string(),
+            source: Source::Json(JsonPath::new(path)?),
+        });
+        Ok(self)
+    }
+
+    /// Captures the value of a response header
+    pub fn capture_header(mut self, name: &str, header: &str) -> Self {
+        self.captures.push(Capture {
+            name: name.to_string(),
+            source: Source::Header(header.to_string()),
+        });
+        self
+    }
+
+    fn render(&self, vars: &HashMap<String, String>) -> Result<Request> {
+        let render = |text: &str| Template::new(text).render(vars);
+
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), render(value)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let body = match &self.body {
+            Some(json) => Some(Body::Json(JsonBody::new(&render(json)?)?)),
+            None => None,
+        };
+
+        Ok(Request {
+            method: self.method.clone(),
+            url: Url::new(&render(&self.url)?)?,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Runs a sequence of requests that share a variable context
+///
+/// Values captured from one response can be referenced as `{{name}}` in the
+/// URL, headers and body of every later step, e.g. a token from a login call.
+pub struct RequestChain<'a> {
+    service: &'a HttpRequestService,
+    vars: HashMap<String, String>,
+}
+
+impl<'a> RequestChain<'a> {
+    pub fn new(service: &'a HttpRequestService) -> Self {
+        Self {
+            service,
+            vars: HashMap::new(),
+        }
+    }
+
+    /// Seeds a variable before the first step runs
+    pub fn with_var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Variables seeded or captured so far
+    pub fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
+    }
+
+    /// Sends one step and records its captures
+    ///
+    /// # Returns
+    /// * `Ok(Response)` - The step's response
+    /// * `Err(anyhow::Error)` - If a placeholder is unset, the request fails or a capture finds nothing
+    pub async fn run(&mut self, step: &ChainStep) -> Result<Response> {
+        let request = step.render(&self.vars)?;
+        let response = self.service.send_request(request).await?;
+
+        for capture in &step.captures {
+            let value = capture.extract(&response)?;
+            self.vars.insert(capture.name.clone(), value);
+        }
+
+        Ok(response)
+    }
+
+    /// Sends every step in order, stopping at the first failure
+    pub async fn run_all(&mut self, steps: &[ChainStep]) -> Result<Vec<Response>> {
+        let mut responses = Vec::with_capacity(steps.len());
+        for step in steps {
+            responses.push(self.run(step).await?);
+        }
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    #[tokio::test]
+    async fn captures_fill_later_steps() {
+        let mut login = MockHttpClient::reply(200, r#"{"token": "abc", "user": {"id": 7}}"#);
+        login
+            .headers
+            .push(("X-Request-Id".to_string(), "r1".to_string()));
+        let mock = MockHttpClient::new()
+            .on(Method::Post, "http://api.test/login", login)
+            .on(
+                Method::Get,
+                "http://api.test/users/7",
+                MockHttpClient::reply(200, "{}"),
+            );
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+        let steps = [
+            ChainStep::new(Method::Post, "http://{{host}}/login")
+                .body(r#"{"user": "{{user}}"}"#)
+                .capture_json("token", "$.token")
+                .unwrap()
+                .capture_json("id", "$.user.id")
+                .unwrap()
+                .capture_header("request", "x-request-id"),
+            ChainStep::new(Method::Get, "http://{{host}}/users/{{id}}")
+                .header("Authorization", "Bearer {{token}}")
+                .header("X-Parent", "{{request}}"),
+        ];
+
+        let mut chain = RequestChain::new(&service)
+            .with_var("host", "api.test")
+            .with_var("user", "ada");
+        chain.run_all(&steps).await.unwrap();
+
+        let calls = mock.calls();
+        let Some(Body::Json(body)) = &calls[0].body else {
+            panic!("expected a JSON body");
+        };
+        assert_eq!(body.0, r#"{"user": "ada"}"#);
+        assert_eq!(
+            calls[1].headers,
+            vec![
+                ("Authorization".to_string(), "Bearer abc".to_string()),
+                ("X-Parent".to_string(), "r1".to_string()),
+            ]
+        );
+        assert_eq!(chain.vars()["id"], "7");
+    }
+
+    #[tokio::test]
+    async fn a_capture_that_finds_nothing_stops_the_chain() {
+        let mock = MockHttpClient::new().on(
+            Method::Get,
+            "http://api.test/a",
+            MockHttpClient::reply(200, "{}"),
+        );
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+        let steps = [
+            ChainStep::new(Method::Get, "http://api.test/a")
+                .capture_json("next", "$.next")
+                .unwrap(),
+            ChainStep::new(Method::Get, "http://api.test/{{next}}"),
+        ];
+
+        let error = RequestChain::new(&service)
+            .run_all(&steps)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Capture 'next' found nothing in the response"
+        );
+        assert_eq!(mock.calls().len(), 1);
+    }
+}
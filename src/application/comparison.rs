@@ -0,0 +1,142 @@
+use crate::domain::entities::Response;
+use std::collections::BTreeMap;
+
+/// A single way in which two responses disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    Status {
+        primary: u16,
+        secondary: u16,
+    },
+    Header {
+        name: String,
+        primary: Option<String>,
+        secondary: Option<String>,
+    },
+    Body,
+}
+
+/// Compares two responses to the same request
+///
+/// # Arguments
+/// * `primary` - Response from the reference URL
+/// * `secondary` - Response from the URL being checked
+/// * `ignore` - Header names to skip, matched case-insensitively
+///
+/// # Returns
+/// * Every difference found, empty when the responses match
+pub fn diff_responses(
+    primary: &Response,
+    secondary: &Response,
+    ignore: &[String],
+) -> Vec<Difference> {
+    let mut differences = Vec::new();
+
+    if primary.status != secondary.status {
+        differences.push(Difference::Status {
+            primary: primary.status.as_u16(),
+            secondary: secondary.status.as_u16(),
+        });
+    }
+
+    let primary_headers = header_values(primary, ignore);
+    let mut secondary_headers = header_values(secondary, ignore);
+    for (name, primary_value) in primary_headers {
+        let secondary_value = secondary_headers.remove(&name);
+        if secondary_value.as_ref() != Some(&primary_value) {
+            differences.push(Difference::Header {
+                name,
+                primary: Some(primary_value),
+                secondary: secondary_value,
+            });
+        }
+    }
+    for (name, secondary_value) in secondary_headers {
+        differences.push(Difference::Header {
+            name,
+            primary: None,
+            secondary: Some(secondary_value),
+        });
+    }
+
+    if primary.body_bytes != secondary.body_bytes {
+        differences.push(Difference::Body);
+    }
+
+    differences
+}
+
+// Lowercased header names mapped to their comma-joined values
+fn header_values(response: &Response, ignore: &[String]) -> BTreeMap<String, String> {
+    let mut values: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in &response.headers {
+        if ignore
+            .iter()
+            .any(|ignored| ignored.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        values
+            .entry(name.to_lowercase())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.clone());
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+        let mut response = MockHttpClient::reply(status, body);
+        response.headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        response
+    }
+
+    #[test]
+    fn matching_responses_have_no_differences() {
+        let primary = response(200, &[("Content-Type", "text/plain"), ("Vary", "a")], "x");
+        let secondary = response(200, &[("content-type", "text/plain"), ("vary", "a")], "x");
+        assert!(diff_responses(&primary, &secondary, &[]).is_empty());
+    }
+
+    #[test]
+    fn status_headers_and_body_differences_are_reported() {
+        let primary = response(200, &[("Server", "a"), ("Vary", "x"), ("Vary", "y")], "one");
+        let secondary = response(404, &[("Vary", "x"), ("Via", "proxy")], "two");
+        let header =
+            |name: &str, primary: Option<&str>, secondary: Option<&str>| Difference::Header {
+                name: name.to_string(),
+                primary: primary.map(str::to_string),
+                secondary: secondary.map(str::to_string),
+            };
+        assert_eq!(
+            diff_responses(&primary, &secondary, &[]),
+            vec![
+                Difference::Status {
+                    primary: 200,
+                    secondary: 404
+                },
+                header("server", Some("a"), None),
+                header("vary", Some("x, y"), Some("x")),
+                header("via", None, Some("proxy")),
+                Difference::Body,
+            ]
+        );
+    }
+
+    #[test]
+    fn ignored_headers_are_skipped() {
+        let primary = response(200, &[("Date", "Mon"), ("ETag", "1")], "");
+        let secondary = response(200, &[("date", "Tue"), ("ETag", "1")], "");
+        assert!(diff_responses(&primary, &secondary, &["DATE".to_string()]).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::domain::entities::{Body, Request};
+use crate::domain::json_path::JsonPath;
+use crate::domain::value_objects::JsonBody;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+/// Builds request variants that differ only in one JSON body field
+#[derive(Debug, Clone)]
+pub struct FieldFuzzer {
+    field: JsonPath,
+}
+
+impl FieldFuzzer {
+    pub fn new(field: JsonPath) -> Self {
+        Self { field }
+    }
+
+    /// Reads substitution values, one per line
+    ///
+    /// A line that parses as JSON (`42`, `null`, `"x"`, `[]`) is used as that
+    /// value; anything else is sent as a string. Blank lines are skipped.
+    pub fn load_values(path: &str) -> Result<Vec<Value>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read fuzz values '{}': {}", path, e))?;
+
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line.trim()).unwrap_or_else(|_| Value::from(line)))
+            .collect())
+    }
+
+    /// Returns a copy of the request with the field set to `value`
+    ///
+    /// # Returns
+    /// * `Ok(Request)` - The variant to send
+    /// * `Err(anyhow::Error)` - If the request has no JSON body or the field does not exist in it
+    pub fn variant(&self, request: &Request, value: &Value) -> Result<Request> {
+        let Some(Body::Json(body)) = &request.body else {
+            return Err(anyhow!("Fuzzing a field needs a JSON request body"));
+        };
+
+        let mut json = serde_json::from_str::<Value>(&body.0)
+            .map_err(|e| anyhow!("Request body is not valid JSON: {}", e))?;
+        let field = self
+            .field
+            .select_mut(&mut json)
+            .ok_or_else(|| anyhow!("The request body has no field at that JSONPath"))?;
+        *field = value.clone();
+
+        Ok(request.with_body(Some(Body::Json(JsonBody::new(&json.to_string())?))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Method;
+    use crate::domain::value_objects::Url;
+    use serde_json::json;
+
+    fn fuzzer(path: &str) -> FieldFuzzer {
+        FieldFuzzer::new(JsonPath::new(path).unwrap())
+    }
+
+    fn post(body: &str) -> Request {
+        Request {
+            method: Method::Post,
+            url: Url::new("http://api.test/users").unwrap(),
+            headers: Vec::new(),
+            body: Some(Body::Json(JsonBody::new(body).unwrap())),
+        }
+    }
+
+    #[test]
+    fn variant_replaces_only_the_field() {
+        let request = post(r#"{"user": {"age": 30, "name": "a"}}"#);
+        let variant = fuzzer("$.user.age")
+            .variant(&request, &json!(null))
+            .unwrap();
+        let Some(Body::Json(body)) = variant.body else {
+            panic!("expected a JSON body");
+        };
+        let body: Value = serde_json::from_str(&body.0).unwrap();
+        assert_eq!(body, json!({"user": {"age": null, "name": "a"}}));
+    }
+
+    #[test]
+    fn variant_needs_a_json_body_with_the_field() {
+        let missing = fuzzer("$.nope").variant(&post("{}"), &json!(1));
+        assert_eq!(
+            missing.unwrap_err().to_string(),
+            "The request body has no field at that JSONPath"
+        );
+
+        let get = Request {
+            method: Method::Get,
+            url: Url::new("http://api.test").unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+        let no_body = fuzzer("$.a").variant(&get, &json!(1));
+        assert_eq!(
+            no_body.unwrap_err().to_string(),
+            "Fuzzing a field needs a JSON request body"
+        );
+    }
+
+    #[test]
+    fn values_parse_as_json_or_fall_back_to_strings() {
+        let path = std::env::temp_dir().join(format!("hurl-fuzz-values-{}", std::process::id()));
+        std::fs::write(&path, "42\n\n\"quoted\"\nplain text\n[]\n").unwrap();
+        let values = FieldFuzzer::load_values(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            values.unwrap(),
+            vec![json!(42), json!("quoted"), json!("plain text"), json!([])]
+        );
+    }
+}
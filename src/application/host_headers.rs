@@ -0,0 +1,128 @@
+use crate::domain::entities::Request;
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+
+/// Default headers per target host, loaded from a `--host-config` file
+///
+/// The file is a JSON object mapping a host, or a `host:port` authority, to
+/// the headers sent to it:
+///
+/// ```json
+/// { "api.example.com": { "X-Api-Key": "secret" }, "localhost:8080": { "X-Debug": "1" } }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HostHeaders {
+    hosts: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl HostHeaders {
+    /// Reads and parses a host config file
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read host config '{}': {}", path, e))?;
+        let hosts: BTreeMap<String, BTreeMap<String, String>> = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid host config '{}': {}", path, e))?;
+
+        Ok(Self {
+            hosts: hosts
+                .into_iter()
+                .map(|(host, headers)| (host.to_lowercase(), headers))
+                .collect(),
+        })
+    }
+
+    /// Adds the headers configured for the request's host
+    ///
+    /// An entry for the full authority (`host:port`) is preferred over one for
+    /// the bare host. Headers the request already has are left alone, so `-H`
+    /// always wins.
+    pub fn apply(&self, request: &mut Request) {
+        let uri = &request.url.0;
+        let Some(host) = uri.host() else {
+            return;
+        };
+        let authority = uri.authority().map(|authority| authority.as_str());
+        let Some(headers) = authority
+            .and_then(|authority| self.hosts.get(&authority.to_lowercase()))
+            .or_else(|| self.hosts.get(&host.to_lowercase()))
+        else {
+            return;
+        };
+
+        for (name, value) in headers {
+            let present = request
+                .headers
+                .iter()
+                .any(|(key, _)| key.eq_ignore_ascii_case(name));
+            if !present {
+                request.headers.push((name.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::{Method, Request};
+    use crate::domain::value_objects::Url;
+
+    fn host_headers(entries: &[(&str, &str, &str)]) -> HostHeaders {
+        let mut hosts: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for (host, name, value) in entries {
+            hosts
+                .entry(host.to_string())
+                .or_default()
+                .insert(name.to_string(), value.to_string());
+        }
+        HostHeaders { hosts }
+    }
+
+    fn headers_for(config: &HostHeaders, url: &str, own: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut request = Request {
+            method: Method::Get,
+            url: Url::new(url).unwrap(),
+            headers: own
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: None,
+        };
+        config.apply(&mut request);
+        request.headers
+    }
+
+    #[test]
+    fn authority_entries_win_over_host_entries() {
+        let config = host_headers(&[
+            ("api.test", "X-Env", "host"),
+            ("api.test:8080", "X-Env", "port"),
+        ]);
+        let pair = |value: &str| vec![("X-Env".to_string(), value.to_string())];
+        assert_eq!(
+            headers_for(&config, "http://API.test:8080/", &[]),
+            pair("port")
+        );
+        assert_eq!(
+            headers_for(&config, "http://api.test:9090/", &[]),
+            pair("host")
+        );
+        assert!(headers_for(&config, "http://other.test/", &[]).is_empty());
+    }
+
+    #[test]
+    fn request_headers_are_not_overridden() {
+        let config = host_headers(&[
+            ("api.test", "X-Api-Key", "default"),
+            ("api.test", "X-Debug", "1"),
+        ]);
+        let headers = headers_for(&config, "http://api.test/", &[("x-api-key", "mine")]);
+        assert_eq!(
+            headers,
+            vec![
+                ("x-api-key".to_string(), "mine".to_string()),
+                ("X-Debug".to_string(), "1".to_string()),
+            ]
+        );
+    }
+}
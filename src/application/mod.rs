@@ -1 +1,9 @@
+pub mod assertions;
+pub mod chain;
+pub mod comparison;
+pub mod fuzz;
+pub mod host_headers;
+pub mod oauth2;
 pub mod services;
+pub mod snippets;
+pub mod summary;
@@ -0,0 +1,131 @@
+use crate::domain::entities::{Body, Method, Request, Response};
+use crate::domain::value_objects::{FormBody, Url};
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::Value;
+
+/// Settings for an OAuth2 client-credentials grant (RFC 6749 section 4.4)
+#[derive(Debug, Clone)]
+pub struct ClientCredentials {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+impl ClientCredentials {
+    /// Builds the token request for the client-credentials grant
+    ///
+    /// The client authenticates with HTTP Basic, as the RFC recommends.
+    pub fn token_request(&self) -> Result<Request> {
+        let mut fields = vec![("grant_type".to_string(), "client_credentials".to_string())];
+        if let Some(scope) = &self.scope {
+            fields.push(("scope".to_string(), scope.clone()));
+        }
+
+        let basic = BASE64.encode(format!(
+            "{}:{}",
+            FormBody::encode(&self.client_id),
+            FormBody::encode(&self.client_secret)
+        ));
+
+        Ok(Request {
+            method: Method::Post,
+            url: Url::new(&self.token_url)?,
+            headers: vec![
+                ("Accept".to_string(), "application/json".to_string()),
+                ("Authorization".to_string(), format!("Basic {}", basic)),
+            ],
+            body: Some(Body::Form(FormBody::new(fields))),
+        })
+    }
+
+    /// Reads the access token from the outcome of the token request
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The `access_token` from the token response
+    /// * `Err(anyhow::Error)` - If the request failed or the response has no token
+    pub fn token_from(&self, outcome: Result<Response>) -> Result<String> {
+        let response = outcome
+            .map_err(|e| anyhow!("OAuth2 token request to {} failed: {}", self.token_url, e))?;
+
+        if !response.status.is_success() {
+            return Err(anyhow!(
+                "OAuth2 token request failed with status {}: {}",
+                response.status,
+                response.text()
+            ));
+        }
+
+        let json = serde_json::from_str::<Value>(&response.text())
+            .map_err(|e| anyhow!("OAuth2 token response is not valid JSON: {}", e))?;
+        json.get("access_token")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("OAuth2 token response has no access_token"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    fn credentials() -> ClientCredentials {
+        ClientCredentials {
+            token_url: "https://auth.test/token".to_string(),
+            client_id: "app id".to_string(),
+            client_secret: "s:cret".to_string(),
+            scope: Some("read write".to_string()),
+        }
+    }
+
+    #[test]
+    fn token_request_posts_the_grant_with_basic_credentials() {
+        let request = credentials().token_request().unwrap();
+        assert!(matches!(request.method, Method::Post));
+        assert_eq!(request.url.as_str(), "https://auth.test/token");
+        // "app+id:s%3Acret", form-encoded before Base64 as RFC 6749 section 2.3.1 asks
+        assert!(request.headers.contains(&(
+            "Authorization".to_string(),
+            "Basic YXBwK2lkOnMlM0FjcmV0".to_string()
+        )));
+        let Some(Body::Form(form)) = &request.body else {
+            panic!("expected a form body, got {:?}", request.body);
+        };
+        assert_eq!(
+            form.as_str(),
+            "grant_type=client_credentials&scope=read+write"
+        );
+    }
+
+    #[test]
+    fn token_from_reads_the_access_token() {
+        let response = MockHttpClient::reply(200, r#"{"access_token":"tok","expires_in":60}"#);
+        assert_eq!(credentials().token_from(Ok(response)).unwrap(), "tok");
+    }
+
+    #[test]
+    fn token_from_rejects_failures_and_responses_without_a_token() {
+        let failed = credentials().token_from(Err(anyhow!("connection refused")));
+        assert_eq!(
+            failed.unwrap_err().to_string(),
+            "OAuth2 token request to https://auth.test/token failed: connection refused"
+        );
+
+        let denied = credentials().token_from(Ok(MockHttpClient::reply(401, "bad client")));
+        assert!(
+            denied
+                .unwrap_err()
+                .to_string()
+                .contains("status 401 Unauthorized: bad client")
+        );
+
+        let empty = credentials().token_from(Ok(MockHttpClient::reply(200, "{}")));
+        assert_eq!(
+            empty.unwrap_err().to_string(),
+            "OAuth2 token response has no access_token"
+        );
+    }
+}
@@ -1,28 +1,290 @@
-use crate::domain::entities::{Request, Response};
+use crate::application::host_headers::HostHeaders;
+use crate::application::oauth2::ClientCredentials;
+use crate::application::summary::SummaryRecorder;
+use crate::domain::entities::{Body, ConnectionInfo, Request, Response};
+use crate::domain::value_objects::Url;
 use anyhow::Result;
 use async_trait::async_trait;
+use hyper::body::Bytes;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+/// Delay before the first retry in `send_with_retry`; doubled on each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Longest wait between two attempts, however many retries are allowed
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Delay before retry number `attempt` (counting from 0): `base`, doubled for
+/// each attempt after the first, and never more than `MAX_RETRY_DELAY`
+pub fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_DELAY)
+}
 
 /// Trait for HTTP clients to enable mocking and dependency inversion
 #[async_trait]
 pub trait HttpClient: Send + Sync {
     async fn send(&self, request: Request) -> Result<Response>;
+
+    /// Sends the request and passes each body chunk to `on_chunk` as it arrives;
+    /// the returned response has status, headers and trailers but an empty body
+    async fn send_streaming(
+        &self,
+        request: Request,
+        on_chunk: &mut (dyn FnMut(Bytes) + Send),
+    ) -> Result<Response>;
+
+    /// Establishes a connection (TCP, plus TLS for https) and closes it without sending a request
+    async fn connect(&self, url: &Url) -> Result<ConnectionInfo>;
 }
 
 /// Application service for orchestrating HTTP request workflows
 /// This contains business logic and use cases
 pub struct HttpRequestService {
     http_client: Box<dyn HttpClient>,
+    host_headers: HostHeaders,
+    oauth2: Option<ClientCredentials>,
+    /// Sends the OAuth2 token request, apart from the client used for the requests themselves
+    token_client: Option<Box<dyn HttpClient>>,
+    /// Access token fetched for `oauth2`, reused for the rest of the run
+    token: OnceCell<String>,
+    summary: Option<SummaryRecorder>,
+    /// Deadline for each request, from connecting until the body has been read
+    timeout: Option<Duration>,
+    max_redirects: usize,
 }
 
 impl HttpRequestService {
     pub fn new(http_client: Box<dyn HttpClient>) -> Self {
-        Self { http_client }
+        Self {
+            http_client,
+            host_headers: HostHeaders::default(),
+            oauth2: None,
+            token_client: None,
+            token: OnceCell::new(),
+            summary: None,
+            timeout: None,
+            max_redirects: 0,
+        }
+    }
+
+    /// Adds default headers per target host, below the request's own headers
+    pub fn with_host_headers(mut self, host_headers: HostHeaders) -> Self {
+        self.host_headers = host_headers;
+        self
+    }
+
+    /// Fetches an OAuth2 token before the first request and sends it as a Bearer header
+    ///
+    /// The token request goes through `token_client`, so transport options meant
+    /// for the user's requests (discarded bodies, trailers, replay) do not apply
+    /// to it. It is still subject to the timeout and recorded in the summary.
+    pub fn with_oauth2(
+        mut self,
+        credentials: ClientCredentials,
+        token_client: Box<dyn HttpClient>,
+    ) -> Self {
+        self.oauth2 = Some(credentials);
+        self.token_client = Some(token_client);
+        self
+    }
+
+    /// Records every request sent through the service in `recorder`
+    pub fn with_summary(mut self, recorder: SummaryRecorder) -> Self {
+        self.summary = Some(recorder);
+        self
+    }
+
+    /// Fails any request (or connection check) that takes longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Follows up to `max_redirects` redirects in `send_request`; 0 returns 3xx responses as-is
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
     }
 
-    /// Sends a simple HTTP request
+    /// Sends an HTTP request, following redirects when enabled with `with_max_redirects`
+    ///
+    /// # Returns
+    /// * `Ok(Response)` - The final response
+    /// * `Err(anyhow::Error)` - If a request fails or the redirect limit is exceeded
     pub async fn send_request(&self, request: Request) -> Result<Response> {
-        self.validate_request(&request)?;
-        self.http_client.send(request).await
+        let origin = request.url.clone();
+        let mut request = request;
+        let mut redirects = 0;
+
+        loop {
+            let mut prepared = request.clone();
+            self.prepare(&mut prepared, request.url.same_origin(&origin)).await?;
+            let response = self.send_prepared(prepared).await?;
+
+            let follow = self.max_redirects > 0 && is_followed(response.status.as_u16());
+            let location = match response.header("location") {
+                Some(location) if follow => location,
+                _ => return Ok(response),
+            };
+            if redirects == self.max_redirects {
+                return Err(anyhow::anyhow!(
+                    "Too many redirects (limit {}); the last pointed to {}",
+                    self.max_redirects,
+                    location
+                ));
+            }
+
+            let next = request.url.join(location)?;
+            debug!(status = %response.status, location = %next.as_str(), "Following redirect");
+            request = redirected_request(&request, response.status.as_u16(), next);
+            // 307 and 308 resend the body, which a pipe no longer has
+            if request.body.as_ref().is_some_and(|body| !body.is_replayable()) {
+                return Err(anyhow::anyhow!(
+                    "Cannot follow the {} redirect to {}: it resends the request body, \
+                     which was streamed and cannot be read again",
+                    response.status.as_u16(),
+                    request.url.as_str()
+                ));
+            }
+            redirects += 1;
+        }
+    }
+
+    /// Sends the request, retrying up to `retries` times on the outcomes `is_retriable` accepts
+    pub async fn send_with_retry(&self, request: Request, retries: u32) -> Result<Response> {
+        self.send_with_retry_with(request, retries, is_retriable).await
+    }
+
+    /// Sends the request, retrying up to `retries` times while `should_retry` accepts the outcome
+    ///
+    /// The predicate sees failures as well as responses, so it can retry on
+    /// anything from a connection error to a particular error code in a 200
+    /// body. Attempts are spaced by a delay that starts at 250ms and doubles,
+    /// up to `MAX_RETRY_DELAY`. A request whose body can only be read once
+    /// (see `Body::is_replayable`) is sent once and never retried.
+    ///
+    /// # Returns
+    /// * The first outcome the predicate rejects, or the last one once the retries run out
+    pub async fn send_with_retry_with<F>(
+        &self,
+        request: Request,
+        retries: u32,
+        should_retry: F,
+    ) -> Result<Response>
+    where
+        F: Fn(&Result<Response>) -> bool,
+    {
+        let replayable = request.body.as_ref().is_none_or(Body::is_replayable);
+        let mut attempt = 0;
+        loop {
+            let outcome = self.send_request(request.clone()).await;
+            if attempt == retries || !should_retry(&outcome) {
+                return outcome;
+            }
+            if !replayable {
+                warn!("Not retrying: the request body was streamed and cannot be read again");
+                return outcome;
+            }
+
+            let delay = retry_delay(attempt);
+            attempt += 1;
+            debug!(attempt, delay_ms = delay.as_millis() as u64, "Retrying request");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn send_prepared(&self, request: Request) -> Result<Response> {
+        self.send_through(self.http_client.as_ref(), request).await
+    }
+
+    // Applies the timeout and records the outcome in the summary
+    async fn send_through(&self, client: &dyn HttpClient, request: Request) -> Result<Response> {
+        let (method, url) = (request.method.clone(), request.url.clone());
+        let result = self.within_timeout(client.send(request)).await;
+
+        if let Some(summary) = &self.summary {
+            let bytes = result
+                .as_ref()
+                .map_or(0, |response| response.body_bytes.len() as u64);
+            summary.record(&method, &url, &result, bytes);
+        }
+        result
+    }
+
+    /// Sends a request and hands each body chunk to `on_chunk` instead of buffering the body;
+    /// redirects are not followed
+    ///
+    /// # Returns
+    /// * `Ok(Response)` - Status, headers and trailers once the body has been read; `body` is empty
+    /// * `Err(anyhow::Error)` - If the request fails or the body cannot be read
+    pub async fn send_streaming(
+        &self,
+        mut request: Request,
+        mut on_chunk: impl FnMut(Bytes) + Send,
+    ) -> Result<Response> {
+        self.prepare(&mut request, true).await?;
+        let (method, url) = (request.method.clone(), request.url.clone());
+        let mut bytes = 0;
+        let mut counted = |chunk: Bytes| {
+            bytes += chunk.len() as u64;
+            on_chunk(chunk);
+        };
+        let result = self
+            .within_timeout(self.http_client.send_streaming(request, &mut counted))
+            .await;
+
+        if let Some(summary) = &self.summary {
+            summary.record(&method, &url, &result, bytes);
+        }
+        result
+    }
+
+    // The OAuth2 token is only sent to the origin of the request it was added for
+    async fn prepare(&self, request: &mut Request, authorize: bool) -> Result<()> {
+        self.validate_request(request)?;
+        self.host_headers.apply(request);
+        if authorize {
+            self.authorize(request).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the server is reachable, without sending an HTTP request
+    pub async fn check_connection(&self, url: &Url) -> Result<ConnectionInfo> {
+        RequestValidator::validate_url(url)?;
+        self.within_timeout(self.http_client.connect(url)).await
+    }
+
+    async fn within_timeout<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        let Some(timeout) = self.timeout else {
+            return future.await;
+        };
+        tokio::time::timeout(timeout, future).await.unwrap_or_else(|_| {
+            Err(anyhow::anyhow!("Request timed out after {}s", timeout.as_secs_f64()))
+        })
+    }
+
+    // An Authorization header set by the caller always wins over the OAuth2 token
+    async fn authorize(&self, request: &mut Request) -> Result<()> {
+        let (Some(credentials), Some(token_client)) = (&self.oauth2, &self.token_client) else {
+            return Ok(());
+        };
+        if request.headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("authorization")) {
+            return Ok(());
+        }
+
+        let token = self
+            .token
+            .get_or_try_init(|| async {
+                let request = credentials.token_request()?;
+                let outcome = self.send_through(token_client.as_ref(), request).await;
+                credentials.token_from(outcome)
+            })
+            .await?;
+        request.headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        Ok(())
     }
 
     fn validate_request(&self, request: &Request) -> Result<()> {
@@ -30,6 +292,53 @@ impl HttpRequestService {
     }
 }
 
+/// Default retry predicate: failed requests, 408 Request Timeout, 429 Too Many
+/// Requests and the 5xx statuses that usually clear up (500, 502, 503, 504)
+pub fn is_retriable(outcome: &Result<Response>) -> bool {
+    match outcome {
+        Ok(response) => matches!(response.status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504),
+        Err(_) => true,
+    }
+}
+
+// 250ms before the first retry, doubling for each one after it up to MAX_RETRY_DELAY
+fn retry_delay(attempt: u32) -> Duration {
+    backoff_delay(RETRY_BASE_DELAY, attempt)
+}
+
+// 300 Multiple Choices and 304 Not Modified carry a Location without asking to follow it
+fn is_followed(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+// 303 always becomes a GET, and like browsers 301/302 turn a POST into a GET;
+// 307/308 resend the same method and body. Credentials are dropped once the
+// redirect leaves the request's origin.
+fn redirected_request(request: &Request, status: u16, url: Url) -> Request {
+    use crate::domain::entities::Method;
+
+    let to_get = match (status, &request.method) {
+        (303, Method::Head) => false,
+        (303, _) => true,
+        (301 | 302, Method::Post) => true,
+        _ => false,
+    };
+    let cross_origin = !request.url.same_origin(&url);
+
+    let mut next = request.with_url(url);
+    if to_get {
+        next = next.with_method(Method::Get).with_body(None);
+    }
+    next.headers.retain(|(key, _)| {
+        let key = key.to_lowercase();
+        let body_header = matches!(key.as_str(), "content-type" | "content-length");
+        let credential = matches!(key.as_str(), "authorization" | "cookie" | "proxy-authorization");
+        let dropped = (to_get && body_header) || (cross_origin && credential);
+        !dropped
+    });
+    next
+}
+
 /// Domain service for request validation
 /// This contains domain business rules
 pub struct RequestValidator;
@@ -41,7 +350,7 @@ impl RequestValidator {
         Ok(())
     }
 
-    fn validate_url(url: &crate::domain::value_objects::Url) -> Result<()> {
+    fn validate_url(url: &Url) -> Result<()> {
       let url_str = url.as_str();
 
       if url_str.is_empty() {
@@ -64,3 +373,305 @@ impl RequestValidator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::{Body, Method, RequestBuilder};
+    use crate::domain::value_objects::{FileBody, JsonBody};
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    fn request(method: Method, url: &str) -> Request {
+        RequestBuilder::new(method).base_url(url).build().unwrap()
+    }
+
+    fn redirect(status: u16, location: &str) -> Response {
+        let mut response = MockHttpClient::reply(status, "");
+        response.headers.push(("Location".to_string(), location.to_string()));
+        response
+    }
+
+    #[test]
+    fn retriable_outcomes_are_errors_and_transient_statuses() {
+        assert!(is_retriable(&Err(anyhow::anyhow!("connection reset"))));
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retriable(&Ok(MockHttpClient::reply(status, ""))), "{}", status);
+        }
+        for status in [200, 301, 400, 404, 501] {
+            assert!(!is_retriable(&Ok(MockHttpClient::reply(status, ""))), "{}", status);
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_from_the_base() {
+        assert_eq!(retry_delay(0), Duration::from_millis(250));
+        assert_eq!(retry_delay(1), Duration::from_millis(500));
+        assert_eq!(retry_delay(3), Duration::from_secs(2));
+        assert_eq!(retry_delay(6), Duration::from_secs(16));
+        assert_eq!(retry_delay(7), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_until_the_outcome_is_not_retriable() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(503, ""))
+            .on_error(Method::Get, "http://a.test/", "connection reset")
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, "ok"));
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+        let started = tokio::time::Instant::now();
+
+        let response = service.send_with_retry(request(Method::Get, "http://a.test/"), 5).await;
+        assert_eq!(response.unwrap().text(), "ok");
+        mock.assert_called(Method::Get, "http://a.test/", 3);
+        assert_eq!(started.elapsed(), Duration::from_millis(750));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_stop_at_the_limit_with_the_last_outcome() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(503, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+
+        let response = service.send_with_retry(request(Method::Get, "http://a.test/"), 2).await;
+        assert_eq!(response.unwrap().status.as_u16(), 503);
+        mock.assert_called(Method::Get, "http://a.test/", 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn custom_predicates_can_retry_successful_responses() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, "busy"))
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, "done"));
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+        let busy = |outcome: &Result<Response>| {
+            outcome.as_ref().is_ok_and(|response| response.text() == "busy")
+        };
+
+        let response = service
+            .send_with_retry_with(request(Method::Get, "http://a.test/"), 3, busy)
+            .await;
+        assert_eq!(response.unwrap().text(), "done");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_responses_fail_at_the_timeout() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, "late"))
+            .with_delay(Duration::from_secs(5));
+        let service = HttpRequestService::new(Box::new(mock.clone()))
+            .with_timeout(Duration::from_millis(1500));
+        let started = tokio::time::Instant::now();
+
+        let response = service.send_request(request(Method::Get, "http://a.test/")).await;
+        assert_eq!(response.unwrap_err().to_string(), "Request timed out after 1.5s");
+        assert_eq!(started.elapsed(), Duration::from_millis(1500));
+        mock.assert_called(Method::Get, "http://a.test/", 1);
+    }
+
+    fn upload(len: Option<u64>) -> Request {
+        let file = FileBody { path: "upload.bin".into(), len };
+        request(Method::Put, "http://a.test/upload").with_body(Some(Body::File(file)))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bodies_that_can_only_be_read_once_are_not_retried() {
+        let mock = MockHttpClient::new()
+            .on(Method::Put, "http://a.test/upload", MockHttpClient::reply(503, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+
+        let piped = service.send_with_retry(upload(None), 3).await;
+        assert_eq!(piped.unwrap().status.as_u16(), 503);
+        mock.assert_called(Method::Put, "http://a.test/upload", 1);
+
+        // A regular file is read again from the start
+        let file = service.send_with_retry(upload(Some(4)), 3).await;
+        assert_eq!(file.unwrap().status.as_u16(), 503);
+        mock.assert_called(Method::Put, "http://a.test/upload", 5);
+    }
+
+    #[tokio::test]
+    async fn redirects_that_resend_a_streamed_body_are_an_error() {
+        let mock = MockHttpClient::new()
+            .on(Method::Put, "http://a.test/upload", redirect(307, "/v2/upload"))
+            .on(Method::Put, "http://a.test/v2/upload", MockHttpClient::reply(201, ""))
+            .on(Method::Put, "http://a.test/moved", redirect(303, "/done"))
+            .on(Method::Get, "http://a.test/done", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone())).with_max_redirects(5);
+
+        let error = service.send_request(upload(None)).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cannot follow the 307 redirect to http://a.test/v2/upload: it resends the \
+             request body, which was streamed and cannot be read again"
+        );
+        mock.assert_called(Method::Put, "http://a.test/v2/upload", 0);
+
+        let file = service.send_request(upload(Some(4))).await.unwrap();
+        assert_eq!(file.status.as_u16(), 201);
+
+        // A 303 drops the body, so there is nothing to resend
+        let moved = upload(None).with_url(Url::new("http://a.test/moved").unwrap());
+        assert_eq!(service.send_request(moved).await.unwrap().status.as_u16(), 200);
+    }
+
+    fn oauth2(token_url: &str) -> ClientCredentials {
+        ClientCredentials {
+            token_url: token_url.to_string(),
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+            scope: None,
+        }
+    }
+
+    fn authorization(request: &Request) -> Option<&str> {
+        request
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("authorization"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_is_fetched_once_and_sent_as_bearer() {
+        let tokens = MockHttpClient::new().on(
+            Method::Post,
+            "http://auth.test/token",
+            MockHttpClient::reply(200, r#"{"access_token":"tok123"}"#),
+        );
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone()))
+            .with_oauth2(oauth2("http://auth.test/token"), Box::new(tokens.clone()));
+
+        service.send_request(request(Method::Get, "http://a.test/")).await.unwrap();
+        service.send_request(request(Method::Get, "http://a.test/")).await.unwrap();
+        tokens.assert_called(Method::Post, "http://auth.test/token", 1);
+        for call in mock.calls() {
+            assert_eq!(authorization(&call), Some("Bearer tok123"));
+        }
+    }
+
+    #[tokio::test]
+    async fn oauth2_leaves_an_explicit_authorization_header_alone() {
+        let tokens = MockHttpClient::new();
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone()))
+            .with_oauth2(oauth2("http://auth.test/token"), Box::new(tokens.clone()));
+        let get = RequestBuilder::new(Method::Get)
+            .base_url("http://a.test/")
+            .basic_auth("user", "pass")
+            .build()
+            .unwrap();
+
+        service.send_request(get).await.unwrap();
+        assert!(tokens.calls().is_empty());
+        assert_eq!(authorization(&mock.calls()[0]), Some("Basic dXNlcjpwYXNz"));
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_is_not_sent_to_other_origins() {
+        let tokens = MockHttpClient::new().on(
+            Method::Post,
+            "http://auth.test/token",
+            MockHttpClient::reply(200, r#"{"access_token":"tok123"}"#),
+        );
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", redirect(302, "http://b.test/"))
+            .on(Method::Get, "http://b.test/", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone()))
+            .with_max_redirects(1)
+            .with_oauth2(oauth2("http://auth.test/token"), Box::new(tokens));
+
+        service.send_request(request(Method::Get, "http://a.test/")).await.unwrap();
+        let calls = mock.calls();
+        assert_eq!(authorization(&calls[0]), Some("Bearer tok123"));
+        assert_eq!(authorization(&calls[1]), None);
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_failures_stop_the_request() {
+        let tokens = MockHttpClient::new()
+            .on(Method::Post, "http://auth.test/token", MockHttpClient::reply(401, "denied"));
+        let mock = MockHttpClient::new();
+        let service = HttpRequestService::new(Box::new(mock.clone()))
+            .with_oauth2(oauth2("http://auth.test/token"), Box::new(tokens));
+
+        let error = service.send_request(request(Method::Get, "http://a.test/")).await;
+        assert!(error.unwrap_err().to_string().contains("status 401 Unauthorized: denied"));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn redirects_are_followed_up_to_the_limit() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/old", redirect(301, "/new"))
+            .on(Method::Get, "http://a.test/new", MockHttpClient::reply(200, "moved"));
+        let service = HttpRequestService::new(Box::new(mock.clone())).with_max_redirects(1);
+
+        let response = service.send_request(request(Method::Get, "http://a.test/old")).await;
+        assert_eq!(response.unwrap().text(), "moved");
+        mock.assert_called(Method::Get, "http://a.test/new", 1);
+    }
+
+    #[tokio::test]
+    async fn redirects_past_the_limit_are_an_error() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/loop", redirect(302, "/loop"));
+        let service = HttpRequestService::new(Box::new(mock.clone())).with_max_redirects(2);
+
+        let error = service.send_request(request(Method::Get, "http://a.test/loop")).await;
+        assert!(error.unwrap_err().to_string().starts_with("Too many redirects (limit 2)"));
+        mock.assert_called(Method::Get, "http://a.test/loop", 3);
+    }
+
+    #[tokio::test]
+    async fn redirects_are_returned_as_is_by_default() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/old", redirect(302, "/new"));
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+
+        let response = service.send_request(request(Method::Get, "http://a.test/old")).await;
+        assert_eq!(response.unwrap().status.as_u16(), 302);
+        mock.assert_called(Method::Get, "http://a.test/new", 0);
+    }
+
+    #[tokio::test]
+    async fn see_other_turns_a_post_into_a_get_without_the_body() {
+        let mock = MockHttpClient::new()
+            .on(Method::Post, "http://a.test/form", redirect(303, "/done"))
+            .on(Method::Get, "http://a.test/done", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone())).with_max_redirects(1);
+        let post = RequestBuilder::new(Method::Post)
+            .base_url("http://a.test/form")
+            .header("Content-Type", "application/json")
+            .body(Body::Json(JsonBody::new("{}").unwrap()))
+            .build()
+            .unwrap();
+
+        service.send_request(post).await.unwrap();
+        let followed = &mock.calls()[1];
+        assert!(matches!(followed.method, Method::Get));
+        assert!(followed.body.is_none());
+        assert!(followed.headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn credentials_are_dropped_when_the_redirect_leaves_the_origin() {
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/", redirect(307, "http://b.test/"))
+            .on(Method::Get, "http://b.test/", MockHttpClient::reply(200, ""));
+        let service = HttpRequestService::new(Box::new(mock.clone())).with_max_redirects(1);
+        let get = RequestBuilder::new(Method::Get)
+            .base_url("http://a.test/")
+            .bearer_token("secret")
+            .header("Accept", "*/*")
+            .build()
+            .unwrap();
+
+        service.send_request(get).await.unwrap();
+        let followed = &mock.calls()[1];
+        assert_eq!(followed.headers, vec![("Accept".to_string(), "*/*".to_string())]);
+    }
+}
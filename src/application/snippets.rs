@@ -0,0 +1,165 @@
+use crate::domain::template::Template;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// A saved request whose URL, header values and body may contain `{{var}}` placeholders
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snippet {
+    /// Inferred from the body when absent, as on the command line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl Snippet {
+    /// Fills every placeholder from `vars`
+    ///
+    /// # Returns
+    /// * `Ok(Snippet)` - A copy with the templates rendered
+    /// * `Err(anyhow::Error)` - If a placeholder has no value
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<Snippet> {
+        let render = |text: &str| Template::new(text).render(vars);
+
+        Ok(Snippet {
+            method: self.method.clone(),
+            url: render(&self.url)?,
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), render(value)?)))
+                .collect::<Result<Vec<_>>>()?,
+            body: self.body.as_deref().map(render).transpose()?,
+        })
+    }
+}
+
+/// Named snippets kept in a JSON file, by default `~/.config/hurl/snippets.json`
+#[derive(Debug)]
+pub struct SnippetStore {
+    path: PathBuf,
+    snippets: BTreeMap<String, Snippet>,
+}
+
+impl SnippetStore {
+    /// Opens the store at `path`, or the default location; a missing file is an empty store
+    pub fn open(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => default_path()?,
+        };
+        let snippets = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| anyhow!("Invalid snippets file '{}': {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to read snippets file '{}': {}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+        Ok(Self { path, snippets })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Snippet> {
+        self.snippets
+            .get(name)
+            .ok_or_else(|| anyhow!("No snippet named '{}' in {}", name, self.path.display()))
+    }
+
+    /// Stores the snippet under `name`, replacing any existing one, and writes the file
+    pub fn save(&mut self, name: &str, snippet: Snippet) -> Result<()> {
+        self.snippets.insert(name.to_string(), snippet);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow!("Failed to create '{}': {}", dir.display(), e))?;
+        }
+        let contents = serde_json::to_string_pretty(&self.snippets)
+            .map_err(|e| anyhow!("Failed to serialize snippets: {}", e))?;
+        std::fs::write(&self.path, contents)
+            .map_err(|e| anyhow!("Failed to write '{}': {}", self.path.display(), e))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+// $XDG_CONFIG_HOME when set, otherwise ~/.config
+fn default_path() -> Result<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(|| anyhow!("Cannot locate the config directory; pass --snippets-file"))?;
+    Ok(config_dir.join("hurl").join("snippets.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet() -> Snippet {
+        Snippet {
+            method: Some("POST".to_string()),
+            url: "https://{{host}}/users".to_string(),
+            headers: vec![("Authorization".to_string(), "Bearer {{token}}".to_string())],
+            body: Some(r#"{"name": "{{name}}"}"#.to_string()),
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn render_fills_url_headers_and_body() {
+        let rendered = snippet()
+            .render(&vars(&[
+                ("host", "api.test"),
+                ("token", "t"),
+                ("name", "Ada"),
+            ]))
+            .unwrap();
+        assert_eq!(rendered.url, "https://api.test/users");
+        assert_eq!(rendered.headers[0].1, "Bearer t");
+        assert_eq!(rendered.body.as_deref(), Some(r#"{"name": "Ada"}"#));
+        assert_eq!(rendered.method.as_deref(), Some("POST"));
+    }
+
+    #[test]
+    fn render_fails_on_a_missing_variable() {
+        let error = snippet()
+            .render(&vars(&[("host", "api.test")]))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Template variable 'token' is not set");
+    }
+
+    #[test]
+    fn store_round_trips_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("hurl-snippets-{}", std::process::id()));
+        let path = dir.join("nested").join("snippets.json");
+        let path = path.to_str().unwrap();
+
+        let mut store = SnippetStore::open(Some(path)).unwrap();
+        assert!(store.get("create").is_err());
+        store.save("create", snippet()).unwrap();
+
+        let reopened = SnippetStore::open(Some(path)).unwrap();
+        let saved = reopened.get("create").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(saved.url, "https://{{host}}/users");
+        assert_eq!(saved.headers, snippet().headers);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::domain::entities::{Method, Response};
+use crate::domain::value_objects::Url;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Outcome of one request in a run
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestSummary {
+    pub method: String,
+    pub url: String,
+    /// Absent when the request failed before a response arrived
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub connect_ms: u64,
+    pub total_ms: u64,
+    /// Size of the response body as received
+    pub bytes: u64,
+}
+
+impl RequestSummary {
+    /// A request succeeds when it got a response with a status below 400
+    pub fn succeeded(&self) -> bool {
+        self.status.is_some_and(|status| status < 400)
+    }
+}
+
+/// Machine-readable summary of every request made during a run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub requests: Vec<RequestSummary>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Wall-clock time since the recorder was created
+    pub total_ms: u64,
+}
+
+impl RunSummary {
+    /// Writes the summary as pretty-printed JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!("Failed to serialize summary: {}", e))?;
+        std::fs::write(path, contents)
+            .map_err(|e| anyhow!("Failed to write summary '{}': {}", path, e))
+    }
+}
+
+/// Shared handle that collects a summary entry for every request sent
+#[derive(Debug, Clone)]
+pub struct SummaryRecorder {
+    started: Instant,
+    requests: Arc<Mutex<Vec<RequestSummary>>>,
+}
+
+impl Default for SummaryRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SummaryRecorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Records the result of one request
+    ///
+    /// # Arguments
+    /// * `bytes` - Body size; passed separately because streamed responses have no body
+    pub fn record(&self, method: &Method, url: &Url, result: &Result<Response>, bytes: u64) {
+        let mut entry = RequestSummary {
+            method: method.as_str().to_string(),
+            url: url.as_str(),
+            status: None,
+            error: None,
+            connect_ms: 0,
+            total_ms: 0,
+            bytes,
+        };
+        match result {
+            Ok(response) => {
+                entry.status = Some(response.status.as_u16());
+                entry.connect_ms = response.timing.connect.as_millis() as u64;
+                entry.total_ms = response.timing.total.as_millis() as u64;
+            }
+            Err(err) => entry.error = Some(err.to_string()),
+        }
+
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(entry);
+        }
+    }
+
+    /// Returns the summary of everything recorded so far
+    pub fn summary(&self) -> RunSummary {
+        let requests = self
+            .requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default();
+        let succeeded = requests.iter().filter(|entry| entry.succeeded()).count();
+
+        RunSummary {
+            failed: requests.len() - succeeded,
+            succeeded,
+            requests,
+            total_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+    use std::time::Duration;
+
+    #[test]
+    fn summary_counts_successes_and_failures() {
+        let recorder = SummaryRecorder::new();
+        let url = Url::new("http://api.test/").unwrap();
+        let mut ok = MockHttpClient::reply(200, "hello");
+        ok.timing.connect = Duration::from_millis(3);
+        ok.timing.total = Duration::from_millis(10);
+        recorder.record(&Method::Get, &url, &Ok(ok), 5);
+        recorder.record(&Method::Get, &url, &Ok(MockHttpClient::reply(404, "")), 0);
+        recorder.record(&Method::Post, &url, &Err(anyhow!("connection refused")), 0);
+
+        let summary = recorder.clone().summary();
+        assert_eq!((summary.succeeded, summary.failed), (1, 2));
+        let first = &summary.requests[0];
+        assert_eq!(
+            (first.status, first.connect_ms, first.total_ms, first.bytes),
+            (Some(200), 3, 10, 5)
+        );
+        let last = &summary.requests[2];
+        assert_eq!(last.method, "POST");
+        assert_eq!(last.status, None);
+        assert_eq!(last.error.as_deref(), Some("connection refused"));
+    }
+}
@@ -0,0 +1,117 @@
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+
+/// Decodes a body to text using the charset it declares
+///
+/// The charset comes from a byte-order mark, then the `charset` parameter of
+/// `content_type`, then an HTML `<meta>` tag. Without any of them the body is
+/// read as UTF-8, with invalid sequences replaced by U+FFFD.
+pub fn decode<'a>(bytes: &'a [u8], content_type: Option<&str>) -> Cow<'a, str> {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (Some(encoding), bom_len),
+        None => {
+            let declared = content_type
+                .and_then(charset_param)
+                .or_else(|| meta_charset(bytes, content_type))
+                .and_then(|label| Encoding::for_label(label.as_bytes()));
+            (declared, 0)
+        }
+    };
+    let bytes = &bytes[bom_len..];
+
+    match encoding {
+        Some(encoding) if encoding != UTF_8 => encoding.decode_without_bom_handling(bytes).0,
+        _ => String::from_utf8_lossy(bytes),
+    }
+}
+
+// The `charset` parameter of a Content-Type value, unquoted
+fn charset_param(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+// Like a browser's prescan, only the first 1024 bytes of an HTML document
+// are searched, for `<meta charset=x>` or `<meta content="...; charset=x">`
+fn meta_charset(bytes: &[u8], content_type: Option<&str>) -> Option<String> {
+    let html = match content_type {
+        Some(content_type) => {
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            mime.eq_ignore_ascii_case("text/html")
+                || mime.eq_ignore_ascii_case("application/xhtml+xml")
+        }
+        None => true,
+    };
+    if !html {
+        return None;
+    }
+
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let value = &tag[tag.find("charset=")? + "charset=".len()..];
+        let label: String = value
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| !matches!(c, '"' | '\'' | ';' | '/' | '>') && !c.is_whitespace())
+            .collect();
+        // A document that could be read as ASCII to find the tag is not UTF-16
+        match label.as_str() {
+            "" => None,
+            "utf-16" | "utf-16le" | "utf-16be" => Some("utf-8".to_string()),
+            _ => Some(label),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bom_wins_over_the_declared_charset() {
+        let bytes = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        assert_eq!(decode(&bytes, Some("text/plain; charset=iso-8859-1")), "hi");
+        assert_eq!(decode(b"\xEF\xBB\xBFcaf\xC3\xA9", None), "café");
+    }
+
+    #[test]
+    fn content_type_charset_is_used() {
+        assert_eq!(
+            decode(b"caf\xE9", Some("text/plain; charset=\"ISO-8859-1\"")),
+            "café"
+        );
+        assert_eq!(decode(b"caf\xE9", Some("text/plain")), "caf\u{FFFD}");
+    }
+
+    #[test]
+    fn html_meta_charset_is_used_for_html_only() {
+        let html = b"<html><head><meta charset=\"windows-1252\"></head>\x80</html>";
+        assert!(decode(html, Some("text/html")).contains('€'));
+        assert!(decode(html, None).contains('€'));
+        assert!(!decode(html, Some("text/plain")).contains('€'));
+
+        let http_equiv =
+            b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=latin1\">\xE9";
+        assert!(decode(http_equiv, Some("text/html")).ends_with('é'));
+    }
+
+    #[test]
+    fn meta_utf16_is_read_as_utf8() {
+        let html = "<meta charset=utf-16>café".as_bytes();
+        assert_eq!(decode(html, Some("text/html")), "<meta charset=utf-16>café");
+    }
+
+    #[test]
+    fn unknown_charsets_fall_back_to_utf8() {
+        assert_eq!(
+            decode("café".as_bytes(), Some("text/plain; charset=klingon")),
+            "café"
+        );
+    }
+}
@@ -1,7 +1,16 @@
-use crate::domain::value_objects::{JsonBody, Url};
+use crate::domain::charset;
+use crate::domain::value_objects::{FileBody, FormBody, JsonBody, RawBody, Url, has_scheme};
 use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use hyper::StatusCode;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
 use std::str::FromStr;
+use std::time::Duration;
+
+/// How much of the body `Response::json` quotes when deserialization fails
+const BODY_SNIPPET_LEN: usize = 120;
 
 /// HTTP method enum for simplicity
 #[derive(Debug, Clone)]
@@ -13,6 +22,8 @@ pub enum Method {
     Patch,
     Head,
     Options,
+    /// Safe, idempotent method that carries its query in the body
+    Query,
 }
 
 impl FromStr for Method {
@@ -27,23 +38,561 @@ impl FromStr for Method {
             "PATCH" => Ok(Method::Patch),
             "HEAD" => Ok(Method::Head),
             "OPTIONS" => Ok(Method::Options),
+            "QUERY" => Ok(Method::Query),
             other => Err(anyhow!("Unsupported HTTP method: '{}'", other)),
         }
     }
 }
 
+impl Method {
+    /// Returns the method name as sent on the request line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Query => "QUERY",
+        }
+    }
+}
+
+/// Request body variants
+#[derive(Debug, Clone)]
+pub enum Body {
+    Json(JsonBody),
+    Form(FormBody),
+    Raw(RawBody),
+    File(FileBody),
+}
+
+impl Body {
+    /// Returns the Content-Type sent when the user does not set one
+    pub fn default_content_type(&self) -> &'static str {
+        match self {
+            Body::Json(_) => "application/json",
+            Body::Form(_) => "application/x-www-form-urlencoded",
+            Body::Raw(_) => "application/octet-stream",
+            Body::File(file) => file.content_type(),
+        }
+    }
+
+    /// Returns the body as bytes, or `None` for bodies streamed from a file
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Json(json) => Some(json.0.as_bytes()),
+            Body::Form(form) => Some(form.as_str().as_bytes()),
+            Body::Raw(raw) => Some(&raw.0),
+            Body::File(_) => None,
+        }
+    }
+
+    /// Whether the body can be sent again, as a retry or a 307/308 redirect needs
+    ///
+    /// Regular files are read again from the start. Files of unknown size,
+    /// such as named pipes, are used up by the first read.
+    pub fn is_replayable(&self) -> bool {
+        !matches!(self, Body::File(file) if file.len.is_none())
+    }
+
+    /// Returns the body length in bytes, or `None` when a file's size is unknown
+    pub fn content_length(&self) -> Option<u64> {
+        match self {
+            Body::File(file) => file.len,
+            body => body.as_bytes().map(|bytes| bytes.len() as u64),
+        }
+    }
+}
+
 /// Represents an HTTP request
 #[derive(Debug, Clone)]
 pub struct Request {
     pub method: Method,
     pub url: Url,
     pub headers: Vec<(String, String)>, // Key-value pairs for headers
-    pub body: Option<JsonBody>,
+    pub body: Option<Body>,
+}
+
+impl Request {
+    /// Returns a copy with the header set, replacing any existing value
+    ///
+    /// The name is matched case-insensitively; the header keeps its original
+    /// position and every other header is left as it was.
+    pub fn with_header(&self, name: &str, value: &str) -> Self {
+        let mut request = self.clone();
+        let mut replaced = false;
+        request.headers.retain_mut(|(key, existing)| {
+            if !key.eq_ignore_ascii_case(name) {
+                return true;
+            }
+            if replaced {
+                return false;
+            }
+            *key = name.to_string();
+            *existing = value.to_string();
+            replaced = true;
+            true
+        });
+        if !replaced {
+            request.headers.push((name.to_string(), value.to_string()));
+        }
+        request
+    }
+
+    /// Returns a copy with the body replaced; `None` removes it
+    pub fn with_body(&self, body: Option<Body>) -> Self {
+        Self {
+            body,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy sent with another method
+    pub fn with_method(&self, method: Method) -> Self {
+        Self {
+            method,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy sent to another URL
+    pub fn with_url(&self, url: Url) -> Self {
+        Self {
+            url,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy sent as POST with the real method in `X-HTTP-Method-Override`,
+    /// for gateways that only pass GET and POST
+    ///
+    /// A POST request is returned unchanged.
+    pub fn with_method_override(&self) -> Self {
+        if matches!(self.method, Method::Post) {
+            return self.clone();
+        }
+        self.with_header("X-HTTP-Method-Override", self.method.as_str())
+            .with_method(Method::Post)
+    }
+}
+
+/// Builds a `Request` from a base URL and a path, e.g. `https://api.example.com/v1` + `users`
+///
+/// # Examples
+///
+/// ```
+/// use hurl::domain::entities::{Method, RequestBuilder};
+///
+/// let api = RequestBuilder::new(Method::Get).base_url("https://api.example.com/v1/");
+///
+/// let users = api.clone().path("/users").build()?;
+/// assert_eq!(users.url.as_str(), "https://api.example.com/v1/users");
+///
+/// let search = api.path("?q=rust").build()?;
+/// assert_eq!(search.url.as_str(), "https://api.example.com/v1/?q=rust");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestBuilder {
+    method: Method,
+    base_url: Option<String>,
+    path: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Option<Body>,
+}
+
+impl RequestBuilder {
+    pub fn new(method: Method) -> Self {
+        Self {
+            method,
+            base_url: None,
+            path: None,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the URL that `path` is joined onto; on its own it is the request URL
+    pub fn base_url(mut self, base: &str) -> Self {
+        self.base_url = Some(base.to_string());
+        self
+    }
+
+    /// Sets the path joined onto the base URL
+    ///
+    /// Exactly one `/` separates the two, however many trailing and leading
+    /// slashes they have. A path that is a full URL (`https://...`) replaces
+    /// the base, and one starting with `?` is appended as the query.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Adds a header; a repeated name is sent repeatedly
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the request body, replacing any body or form set before
+    pub fn body(mut self, body: Body) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Sets an `application/x-www-form-urlencoded` body built from the fields
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.body(Body::Form(FormBody::new(fields)))
+    }
+
+    /// Adds an `Authorization: Basic` header carrying the credentials
+    ///
+    /// `build` fails if another Authorization header was set as well.
+    pub fn basic_auth(self, user: &str, pass: &str) -> Self {
+        self.header("Authorization", &basic_authorization(user, pass))
+    }
+
+    /// Adds an `Authorization: Bearer` header carrying the token
+    ///
+    /// `build` fails if another Authorization header was set as well.
+    pub fn bearer_token(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Joins the URL and creates the request
+    ///
+    /// # Returns
+    /// * `Ok(Request)` - The request, with a parsed URL
+    /// * `Err(anyhow::Error)` - If there is no URL, a relative path has no base, the result is
+    ///   invalid or more than one Authorization header was set
+    pub fn build(self) -> Result<Request> {
+        let authorizations = self
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .count();
+        if authorizations > 1 {
+            return Err(anyhow!(
+                "More than one Authorization header was set; use only one of basic_auth, bearer_token or header"
+            ));
+        }
+
+        let url = match (self.base_url.as_deref(), self.path.as_deref()) {
+            (_, Some(path)) if has_scheme(path) => path.to_string(),
+            (Some(base), Some(path)) => join_url(base, path),
+            (Some(base), None) => base.to_string(),
+            (None, Some(path)) => {
+                return Err(anyhow!("Relative path '{}' needs a base URL", path));
+            }
+            (None, None) => return Err(anyhow!("RequestBuilder needs a base URL or path")),
+        };
+
+        Ok(Request {
+            method: self.method,
+            url: Url::new(&url)?,
+            headers: self.headers,
+            body: self.body,
+        })
+    }
+}
+
+/// Formats the `Authorization` value for HTTP Basic credentials (RFC 7617)
+pub fn basic_authorization(user: &str, pass: &str) -> String {
+    format!("Basic {}", BASE64.encode(format!("{}:{}", user, pass)))
+}
+
+fn join_url(base: &str, path: &str) -> String {
+    if path.is_empty() || path.starts_with('?') {
+        return format!("{}{}", base, path);
+    }
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Time spent on the phases of a request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timing {
+    /// Establishing the TCP (and TLS) connection
+    pub connect: Duration,
+    /// From starting to connect until the whole body was read
+    pub total: Duration,
 }
 
 /// Represents an HTTP response
 #[derive(Debug, Clone)]
 pub struct Response {
     pub status: StatusCode,
-    pub body: String,
+    pub headers: Vec<(String, String)>, // Key-value pairs for headers
+    /// The body exactly as received
+    pub body_bytes: Vec<u8>,
+    pub trailers: Vec<(String, String)>, // Headers sent after a chunked body
+    pub timing: Timing,
+    /// The TLS session the response arrived over, for https
+    pub tls: Option<TlsInfo>,
+}
+
+impl Response {
+    /// Returns the first value of a header, matching the name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the body as text, decoded with the charset from the headers or the body
+    ///
+    /// Bytes that are not valid in that charset are replaced with U+FFFD, so
+    /// binary bodies should be read from `body_bytes` instead.
+    pub fn text(&self) -> Cow<'_, str> {
+        charset::decode(&self.body_bytes, self.header("content-type"))
+    }
+
+    /// Deserializes the JSON body into `T`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hurl::domain::entities::{Response, Timing};
+    /// # use hyper::StatusCode;
+    /// #[derive(serde::Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// # let response = Response {
+    /// #     status: StatusCode::OK,
+    /// #     headers: Vec::new(),
+    /// #     body_bytes: br#"{"name":"Ada","id":1}"#.to_vec(),
+    /// #     trailers: Vec::new(),
+    /// #     timing: Timing::default(),
+    /// #     tls: None,
+    /// # };
+    /// let user: User = response.json()?;
+    /// assert_eq!(user.name, "Ada");
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    ///
+    /// # Returns
+    /// * `Ok(T)` - The deserialized body
+    /// * `Err(anyhow::Error)` - If the body does not match `T`; the message quotes the start of the body
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        let body = self.text();
+        serde_json::from_str(&body).map_err(|e| {
+            let end = body.floor_char_boundary(BODY_SNIPPET_LEN);
+            let ellipsis = if end < body.len() { "..." } else { "" };
+            anyhow!(
+                "Failed to deserialize response body: {} (body: {:?}{})",
+                e,
+                &body[..end],
+                ellipsis
+            )
+        })
+    }
+}
+
+/// Outcome of establishing a connection without sending a request
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    /// Address actually connected to
+    pub peer: String,
+    /// Present when a TLS session was negotiated
+    pub tls: Option<TlsInfo>,
+    /// Time taken to connect, including the TLS handshake
+    pub elapsed: Duration,
+}
+
+/// Details of a negotiated TLS session
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    /// Name sent as SNI and verified against the certificate
+    pub server_name: String,
+    /// Protocol selected by the server over ALPN, if any
+    pub alpn: Option<String>,
+    /// TLS version in use, e.g. `TLSv1.3`, when it could be determined
+    pub protocol: Option<String>,
+    /// Cipher suite in use, e.g. `TLS_AES_128_GCM_SHA256`, when it could be determined
+    pub cipher: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_auth_encodes_the_credentials() {
+        let request = RequestBuilder::new(Method::Get)
+            .base_url("https://example.com")
+            .basic_auth("user", "pass")
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.headers,
+            vec![(
+                "Authorization".to_string(),
+                "Basic dXNlcjpwYXNz".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn form_sets_an_urlencoded_body() {
+        let request = RequestBuilder::new(Method::Post)
+            .base_url("https://example.com")
+            .form(&[("q", "rust lang"), ("page", "2")])
+            .build()
+            .unwrap();
+        let Some(Body::Form(form)) = &request.body else {
+            panic!("expected a form body, got {:?}", request.body);
+        };
+        assert_eq!(form.as_str(), "q=rust+lang&page=2");
+        assert_eq!(
+            request.body.as_ref().unwrap().default_content_type(),
+            "application/x-www-form-urlencoded"
+        );
+    }
+
+    #[test]
+    fn a_second_authorization_header_is_an_error() {
+        let both = RequestBuilder::new(Method::Get)
+            .base_url("https://example.com")
+            .basic_auth("user", "pass")
+            .bearer_token("abc");
+        assert!(both.build().is_err());
+
+        let explicit = RequestBuilder::new(Method::Get)
+            .base_url("https://example.com")
+            .header("authorization", "Custom x")
+            .bearer_token("abc");
+        assert!(explicit.build().is_err());
+    }
+
+    #[test]
+    fn with_header_replaces_in_place_and_leaves_the_original_alone() {
+        let original = Request {
+            method: Method::Get,
+            url: Url::new("https://example.com").unwrap(),
+            headers: vec![
+                ("accept".to_string(), "text/html".to_string()),
+                ("X-Trace".to_string(), "1".to_string()),
+                ("ACCEPT".to_string(), "text/plain".to_string()),
+            ],
+            body: None,
+        };
+        let changed = original.with_header("Accept", "application/json");
+        assert_eq!(
+            changed.headers,
+            vec![
+                ("Accept".to_string(), "application/json".to_string()),
+                ("X-Trace".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(original.headers.len(), 3);
+        assert_eq!(
+            changed.with_header("X-New", "y").headers.last().unwrap().0,
+            "X-New"
+        );
+    }
+
+    #[test]
+    fn method_override_sends_a_post() {
+        let delete = RequestBuilder::new(Method::Delete)
+            .base_url("https://example.com/items/1")
+            .build()
+            .unwrap();
+        let overridden = delete.with_method_override();
+        assert!(matches!(overridden.method, Method::Post));
+        assert_eq!(
+            overridden.headers,
+            vec![("X-HTTP-Method-Override".to_string(), "DELETE".to_string())]
+        );
+
+        let post = delete.with_method(Method::Post).with_method_override();
+        assert!(post.headers.is_empty());
+    }
+
+    #[test]
+    fn json_errors_quote_the_start_of_the_body() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct User {
+            id: u64,
+        }
+
+        let response = Response {
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body_bytes: format!(r#"{{"id": "seven", "pad": "{}"}}"#, "x".repeat(200)).into_bytes(),
+            trailers: Vec::new(),
+            timing: Timing::default(),
+            tls: None,
+        };
+        let error = response.json::<User>().unwrap_err().to_string();
+        assert!(
+            error.starts_with("Failed to deserialize response body: invalid type"),
+            "{}",
+            error
+        );
+        assert!(error.ends_with("...)"), "{}", error);
+    }
+
+    fn joined(base: Option<&str>, path: Option<&str>) -> Result<String> {
+        let mut builder = RequestBuilder::new(Method::Get);
+        if let Some(base) = base {
+            builder = builder.base_url(base);
+        }
+        if let Some(path) = path {
+            builder = builder.path(path);
+        }
+        builder.build().map(|request| request.url.as_str())
+    }
+
+    #[test]
+    fn paths_join_with_exactly_one_slash() {
+        for (base, path) in [
+            ("https://api.test/v1", "users"),
+            ("https://api.test/v1/", "/users"),
+            ("https://api.test/v1//", "//users"),
+        ] {
+            assert_eq!(
+                joined(Some(base), Some(path)).unwrap(),
+                "https://api.test/v1/users"
+            );
+        }
+        assert_eq!(
+            joined(Some("https://api.test/v1"), Some("")).unwrap(),
+            "https://api.test/v1"
+        );
+    }
+
+    #[test]
+    fn full_urls_replace_the_base_and_relative_paths_need_one() {
+        assert_eq!(
+            joined(Some("https://api.test/v1"), Some("http://other.test/x")).unwrap(),
+            "http://other.test/x"
+        );
+        assert_eq!(
+            joined(None, Some("https://api.test/")).unwrap(),
+            "https://api.test/"
+        );
+        assert_eq!(
+            joined(Some("http://api.test"), Some("/cb?u=http://x")).unwrap(),
+            "http://api.test/cb?u=http://x"
+        );
+        assert_eq!(
+            joined(None, Some("users")).unwrap_err().to_string(),
+            "Relative path 'users' needs a base URL"
+        );
+        assert!(joined(None, None).is_err());
+    }
 }
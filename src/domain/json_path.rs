@@ -0,0 +1,142 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// One step of a JSONPath: an object key or an array index
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Represents a simple JSONPath such as `$.data.items[0].name`
+///
+/// Supports dotted keys, `[N]` indices and quoted `['key']` segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// Parses a JSONPath expression
+    ///
+    /// # Arguments
+    /// * `path` - Expression starting with `$`
+    ///
+    /// # Returns
+    /// * `Ok(JsonPath)` - Parsed path
+    /// * `Err(anyhow::Error)` - If the expression is malformed
+    pub fn new(path: &str) -> Result<Self> {
+        let invalid = |reason: &str| anyhow!("Invalid JSONPath '{}': {}", path, reason);
+        let mut rest = path
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with '$'"))?;
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid("empty key"));
+                }
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                let inner = after[..end].trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|key| key.strip_suffix('\''))
+                    .or_else(|| {
+                        inner
+                            .strip_prefix('"')
+                            .and_then(|key| key.strip_suffix('"'))
+                    });
+                let segment = match quoted {
+                    Some(key) => Segment::Key(key.to_string()),
+                    None => Segment::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| invalid("index must be a number or a quoted key"))?,
+                    ),
+                };
+                segments.push(segment);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    /// Returns the value the path points at, if it exists
+    pub fn select<'a>(&self, json: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(json, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value.get(index),
+            })
+    }
+
+    /// Returns a mutable reference to the value the path points at, if it exists
+    pub fn select_mut<'a>(&self, json: &'a mut Value) -> Option<&'a mut Value> {
+        self.segments
+            .iter()
+            .try_fold(json, |value, segment| match segment {
+                Segment::Key(key) => value.get_mut(key),
+                Segment::Index(index) => value.get_mut(index),
+            })
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = anyhow::Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        JsonPath::new(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selects_keys_indices_and_quoted_keys() {
+        let json = json!({"data": {"items": [{"name": "a"}, {"name": "b"}], "a.b": 1}});
+        let select = |path: &str| JsonPath::new(path).unwrap().select(&json).cloned();
+        assert_eq!(select("$.data.items[1].name"), Some(json!("b")));
+        assert_eq!(select("$.data['a.b']"), Some(json!(1)));
+        assert_eq!(select("$[\"data\"].items[0]"), Some(json!({"name": "a"})));
+        assert_eq!(select("$"), Some(json.clone()));
+        assert_eq!(select("$.data.items[5]"), None);
+    }
+
+    #[test]
+    fn select_mut_changes_the_value() {
+        let mut json = json!({"user": {"id": 1}});
+        *JsonPath::new("$.user.id")
+            .unwrap()
+            .select_mut(&mut json)
+            .unwrap() = json!(2);
+        assert_eq!(json, json!({"user": {"id": 2}}));
+    }
+
+    #[test]
+    fn malformed_paths_are_errors() {
+        for (path, reason) in [
+            ("data", "must start with '$'"),
+            ("$..a", "empty key"),
+            ("$[0", "unclosed '['"),
+            ("$[x]", "index must be a number or a quoted key"),
+            ("$a", "expected '.' or '['"),
+        ] {
+            let error = JsonPath::new(path).unwrap_err().to_string();
+            assert_eq!(error, format!("Invalid JSONPath '{}': {}", path, reason));
+        }
+    }
+}
@@ -1,2 +1,5 @@
+pub mod charset;
 pub mod entities;
+pub mod json_path;
+pub mod template;
 pub mod value_objects;
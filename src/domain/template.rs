@@ -0,0 +1,83 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+
+/// Represents text containing `{{name}}` placeholders
+#[derive(Debug, Clone)]
+pub struct Template(pub String);
+
+impl Template {
+    pub fn new(source: &str) -> Self {
+        Template(source.to_string())
+    }
+
+    /// Substitutes every placeholder with its variable value
+    ///
+    /// # Arguments
+    /// * `vars` - Variable names mapped to their values
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The rendered text
+    /// * `Err(anyhow::Error)` - If a placeholder is unterminated or its variable is unset
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        let mut rendered = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| anyhow!("Unterminated placeholder in template"))?;
+            let name = after[..end].trim();
+            let value = vars
+                .get(name)
+                .ok_or_else(|| anyhow!("Template variable '{}' is not set", name))?;
+            rendered.push_str(value);
+            rest = &after[end + 2..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let template = Template::new(r#"{"id": {{ id }}, "name": "{{name}}{{name}}"}"#);
+        assert_eq!(
+            template
+                .render(&vars(&[("id", "7"), ("name", "ab")]))
+                .unwrap(),
+            r#"{"id": 7, "name": "abab"}"#
+        );
+        assert_eq!(
+            Template::new("no placeholders").render(&vars(&[])).unwrap(),
+            "no placeholders"
+        );
+    }
+
+    #[test]
+    fn unset_and_unterminated_placeholders_are_errors() {
+        let unset = Template::new("{{missing}}").render(&vars(&[]));
+        assert_eq!(
+            unset.unwrap_err().to_string(),
+            "Template variable 'missing' is not set"
+        );
+        let open = Template::new("{{id").render(&vars(&[("id", "1")]));
+        assert_eq!(
+            open.unwrap_err().to_string(),
+            "Unterminated placeholder in template"
+        );
+    }
+}
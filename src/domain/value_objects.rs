@@ -1,6 +1,10 @@
 use anyhow::{anyhow, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use hyper::http::Uri;
+use hyper::http::uri::PathAndQuery;
 use serde_json::Value;
+use std::path::PathBuf;
 
 /// Represents a validated URL
 #[derive(Debug, Clone)]
@@ -25,6 +29,105 @@ impl Url {
     pub fn as_str(&self) -> String {
         self.0.to_string()
     }
+
+    /// Resolves a reference such as a `Location` header against this URL
+    ///
+    /// Handles absolute URLs, scheme-relative `//host/path`, absolute paths,
+    /// query-only references and paths relative to the current directory.
+    pub fn join(&self, reference: &str) -> Result<Self> {
+        let reference = reference.trim();
+        if has_scheme(reference) {
+            return Url::new(reference);
+        }
+
+        let scheme = self.0.scheme_str().unwrap_or("http");
+        let authority = self
+            .0
+            .authority()
+            .map(|authority| authority.as_str())
+            .unwrap_or_default();
+        let path = self.0.path();
+
+        let joined = if reference.starts_with("//") {
+            format!("{}:{}", scheme, reference)
+        } else if reference.starts_with('/') {
+            format!("{}://{}{}", scheme, authority, reference)
+        } else if reference.starts_with('?') {
+            format!("{}://{}{}{}", scheme, authority, path, reference)
+        } else if reference.is_empty() || reference.starts_with('#') {
+            return Ok(self.clone());
+        } else {
+            let directory = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+            format!("{}://{}{}{}", scheme, authority, directory, reference)
+        };
+        Url::new(&joined)
+    }
+
+    /// Appends `key=value` to the query string, percent-encoding both
+    ///
+    /// Existing parameters are kept and the new one goes after them, so
+    /// `&`, `=` and spaces in the key or value cannot split or merge
+    /// parameters. An empty value is sent as `key=`.
+    ///
+    /// # Returns
+    /// * `Ok(Url)` - The URL with the parameter added
+    /// * `Err(anyhow::Error)` - If the resulting URL is invalid
+    pub fn with_query_param(self, key: &str, value: &str) -> Result<Self> {
+        let param = format!("{}={}", percent_encode(key, false), percent_encode(value, false));
+        let path_and_query = match self.0.query() {
+            Some(query) if !query.is_empty() => {
+                format!("{}?{}&{}", self.0.path(), query, param)
+            }
+            _ => format!("{}?{}", self.0.path(), param),
+        };
+
+        let mut parts = self.0.into_parts();
+        parts.path_and_query = Some(
+            path_and_query
+                .parse::<PathAndQuery>()
+                .map_err(|e| anyhow!("Invalid URL: {}", e))?,
+        );
+        Uri::from_parts(parts)
+            .map(Url)
+            .map_err(|e| anyhow!("Invalid URL: {}", e))
+    }
+
+    /// Whether both URLs have the same scheme, host and port
+    pub fn same_origin(&self, other: &Url) -> bool {
+        self.0.scheme_str() == other.0.scheme_str()
+            && self.0.host().map(str::to_lowercase) == other.0.host().map(str::to_lowercase)
+            && self.0.port_u16() == other.0.port_u16()
+    }
+}
+
+// RFC 3986 escaping: unreserved bytes pass through and everything else is
+// percent-encoded, except that form fields may send spaces as '+'
+fn percent_encode(value: &str, space_as_plus: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b' ' if space_as_plus => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Whether a URL reference starts with a scheme such as `https:`, making it absolute
+///
+/// Only a scheme before the first `/`, `?` or `#` counts (RFC 3986 section 3.1),
+/// so `/login?next=https://a.test/` is still a relative reference.
+pub fn has_scheme(reference: &str) -> bool {
+    let Some(end) = reference.find([':', '/', '?', '#']) else {
+        return false;
+    };
+    let scheme = &reference[..end];
+    reference[end..].starts_with(':')
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
 }
 
 /// Represents a validated JSON body
@@ -38,7 +141,7 @@ impl JsonBody {
     /// * `json` - The JSON string to validate
     ///
     /// # Returns
-    /// * `Ok	JsonBody)` - Validated JSON
+    /// * `Ok(JsonBody)` - Validated JSON
     /// * `Err(anyhow::Error)` - If the JSON is invalid
     pub fn new(json: &str) -> Result<Self> {
         serde_json::from_str::<Value>(json)
@@ -46,3 +149,278 @@ impl JsonBody {
         Ok(JsonBody(json.to_string()))
     }
 }
+
+/// Represents an `application/x-www-form-urlencoded` body
+#[derive(Debug, Clone)]
+pub struct FormBody {
+    pub fields: Vec<(String, String)>,
+    encoded: String,
+}
+
+impl FormBody {
+    /// Creates a FormBody, encoding the fields in the order given
+    ///
+    /// # Arguments
+    /// * `fields` - Name and value pairs; a repeated name is sent repeatedly
+    pub fn new(fields: Vec<(String, String)>) -> Self {
+        let encoded = fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", Self::encode(name), Self::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+        FormBody { fields, encoded }
+    }
+
+    /// Returns the encoded body, e.g. `name=Jane+Doe&role=admin`
+    pub fn as_str(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Escapes a single field name or value the way the body does, e.g. `Jane+Doe`
+    pub fn encode(value: &str) -> String {
+        percent_encode(value, true)
+    }
+}
+
+/// Represents an opaque binary body
+#[derive(Debug, Clone)]
+pub struct RawBody(pub Vec<u8>);
+
+impl RawBody {
+    /// Decodes a hexadecimal string into a RawBody
+    ///
+    /// # Arguments
+    /// * `hex` - Hex digits, optionally separated by whitespace
+    ///
+    /// # Returns
+    /// * `Ok(RawBody)` - Decoded bytes
+    /// * `Err(anyhow::Error)` - If the input is not valid hex
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(anyhow!("Invalid hex: odd number of digits"));
+        }
+
+        digits
+            .chunks(2)
+            .map(|pair| Ok((hex_digit(pair[0])? << 4) | hex_digit(pair[1])?))
+            .collect::<Result<Vec<u8>>>()
+            .map(RawBody)
+    }
+
+    /// Decodes a standard base64 string into a RawBody
+    ///
+    /// # Arguments
+    /// * `encoded` - The base64 string to decode
+    ///
+    /// # Returns
+    /// * `Ok(RawBody)` - Decoded bytes
+    /// * `Err(anyhow::Error)` - If the input is not valid base64
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        BASE64
+            .decode(encoded.trim())
+            .map(RawBody)
+            .map_err(|e| anyhow!("Invalid base64: {}", e))
+    }
+}
+
+fn hex_digit(digit: u8) -> Result<u8> {
+    (digit as char)
+        .to_digit(16)
+        .map(|value| value as u8)
+        .ok_or_else(|| anyhow!("Invalid hex digit: '{}'", digit as char))
+}
+
+/// Represents a file streamed from disk as the request body
+#[derive(Debug, Clone)]
+pub struct FileBody {
+    pub path: PathBuf,
+    /// Size in bytes, known for regular files
+    pub len: Option<u64>,
+}
+
+impl FileBody {
+    /// Creates a new FileBody after checking the file can be read
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to upload
+    ///
+    /// # Returns
+    /// * `Ok(FileBody)` - File metadata was read
+    /// * `Err(anyhow::Error)` - If the file does not exist or cannot be accessed
+    pub fn new(path: &str) -> Result<Self> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| anyhow!("Failed to read file '{}': {}", path, e))?;
+        let len = metadata.is_file().then_some(metadata.len());
+        Ok(FileBody {
+            path: PathBuf::from(path),
+            len,
+        })
+    }
+
+    /// Creates a FileBody for a named pipe, read until the writer closes it
+    ///
+    /// # Returns
+    /// * `Ok(FileBody)` - The path is a FIFO
+    /// * `Err(anyhow::Error)` - If the path does not exist or is not a FIFO
+    pub fn fifo(path: &str) -> Result<Self> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| anyhow!("Failed to read FIFO '{}': {}", path, e))?;
+        if !is_fifo(&metadata) {
+            return Err(anyhow!("'{}' is not a named pipe (create one with mkfifo)", path));
+        }
+        Ok(FileBody {
+            path: PathBuf::from(path),
+            len: None,
+        })
+    }
+
+    /// Returns the file name, used when the upload URL names a directory
+    pub fn file_name(&self) -> Option<String> {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    /// Guesses the Content-Type from the file extension, falling back to
+    /// `application/octet-stream` for unknown or missing extensions
+    pub fn content_type(&self) -> &'static str {
+        mime_guess::from_path(&self.path)
+            .first_raw()
+            .unwrap_or("application/octet-stream")
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_fifo()
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_leading_scheme_makes_a_reference_absolute() {
+        for reference in ["https://a.test/", "HTTP://a.test", "svn+ssh://h/", "mailto:x@a.test"] {
+            assert!(has_scheme(reference), "{}", reference);
+        }
+        for reference in ["/login?next=https://a.test/", "cb?u=http://x", "//a.test/", "", "1a:b"] {
+            assert!(!has_scheme(reference), "{}", reference);
+        }
+    }
+
+    #[test]
+    fn raw_bodies_decode_hex_and_base64() {
+        assert_eq!(RawBody::from_hex("de AD\nbe ef").unwrap().0, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(RawBody::from_base64(" aGk=\n").unwrap().0, b"hi");
+    }
+
+    #[test]
+    fn malformed_raw_bodies_are_errors() {
+        assert_eq!(
+            RawBody::from_hex("abc").unwrap_err().to_string(),
+            "Invalid hex: odd number of digits"
+        );
+        assert_eq!(RawBody::from_hex("zz").unwrap_err().to_string(), "Invalid hex digit: 'z'");
+        assert!(RawBody::from_base64("not base64!").is_err());
+    }
+
+    #[test]
+    fn encoded_slashes_and_dot_segments_are_kept_verbatim() {
+        let raw = "http://a.test/a%2Fb/../c/./d?x=%2F";
+        let url = Url::new(raw).unwrap();
+        assert_eq!(url.as_str(), raw);
+        assert_eq!(url.0.path_and_query().unwrap(), "/a%2Fb/../c/./d?x=%2F");
+        assert_eq!(join("http://a.test/", "/a%2Fb/../c"), "http://a.test/a%2Fb/../c");
+    }
+
+    fn file(path: &str) -> FileBody {
+        FileBody { path: PathBuf::from(path), len: None }
+    }
+
+    fn join(base: &str, reference: &str) -> String {
+        Url::new(base).unwrap().join(reference).unwrap().as_str()
+    }
+
+    #[test]
+    fn join_resolves_each_kind_of_reference() {
+        let base = "http://a.test/docs/page?x=1";
+        assert_eq!(join(base, "https://b.test/"), "https://b.test/");
+        assert_eq!(join(base, "//c.test/p"), "http://c.test/p");
+        assert_eq!(join(base, "/root"), "http://a.test/root");
+        assert_eq!(join(base, "?y=2"), "http://a.test/docs/page?y=2");
+        assert_eq!(join(base, "other"), "http://a.test/docs/other");
+        assert_eq!(
+            join(base, "/login?next=https://a.test/"),
+            "http://a.test/login?next=https://a.test/"
+        );
+    }
+
+    #[test]
+    fn join_keeps_the_url_for_empty_and_fragment_references() {
+        let base = "http://a.test:8080/docs/";
+        assert_eq!(join(base, ""), base);
+        assert_eq!(join(base, "#top"), base);
+        assert_eq!(join(base, " next "), "http://a.test:8080/docs/next");
+    }
+
+    fn with_query(url: &str, key: &str, value: &str) -> String {
+        Url::new(url).unwrap().with_query_param(key, value).unwrap().as_str()
+    }
+
+    #[test]
+    fn query_params_are_appended() {
+        assert_eq!(with_query("http://a.test", "q", "1"), "http://a.test/?q=1");
+        assert_eq!(with_query("http://a.test/p?x=1", "y", "2"), "http://a.test/p?x=1&y=2");
+        assert_eq!(with_query("http://a.test/p?", "y", "2"), "http://a.test/p?y=2");
+    }
+
+    #[test]
+    fn query_params_are_percent_encoded() {
+        assert_eq!(
+            with_query("http://a.test/", "a b&c", "x=y/z~é"),
+            "http://a.test/?a%20b%26c=x%3Dy%2Fz~%C3%A9"
+        );
+    }
+
+    #[test]
+    fn form_fields_are_encoded_in_order() {
+        let fields = vec![
+            ("name".to_string(), "Jane Doe".to_string()),
+            ("tags".to_string(), "a&b=c".to_string()),
+            ("name".to_string(), "x+y*".to_string()),
+        ];
+        assert_eq!(
+            FormBody::new(fields).as_str(),
+            "name=Jane+Doe&tags=a%26b%3Dc&name=x%2By%2A"
+        );
+    }
+
+    #[test]
+    fn form_encoding_differs_from_query_encoding_only_for_spaces() {
+        assert_eq!(FormBody::encode("a b~"), "a+b~");
+        assert_eq!(FormBody::encode(""), "");
+        assert_eq!(FormBody::new(Vec::new()).as_str(), "");
+    }
+
+    #[test]
+    fn content_type_comes_from_the_extension() {
+        assert_eq!(file("report.json").content_type(), "application/json");
+        assert_eq!(file("photo.PNG").content_type(), "image/png");
+        assert_eq!(file("dir/page.html").content_type(), "text/html");
+        assert_eq!(file("export.csv").content_type(), "text/csv");
+    }
+
+    #[test]
+    fn content_type_falls_back_to_octet_stream() {
+        assert_eq!(file("archive.unknownext").content_type(), "application/octet-stream");
+        assert_eq!(file("Makefile").content_type(), "application/octet-stream");
+    }
+}
@@ -1,75 +1,542 @@
-use crate::domain::entities::{Method as DomainMethod, Request, Response};
-use crate::domain::value_objects::JsonBody;
+use crate::application::services::backoff_delay;
+use crate::domain::entities::{
+    Body, ConnectionInfo, Method as DomainMethod, Request, Response, Timing, TlsInfo,
+};
+use crate::domain::value_objects::Url;
+use crate::infrastructure::transcript::{Direction, TranscriptRecorder, WireTranscript};
 
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
-use http_body_util::{BodyExt, Full};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::stream;
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
 use hyper::body::Bytes;
-use hyper::header::{CONTENT_TYPE, HOST, HeaderValue};
+use hyper::body::Frame;
+use hyper::header::{
+    CONTENT_LENGTH, CONTENT_TYPE, HOST, HeaderMap, HeaderName, HeaderValue, TRAILER,
+    TRANSFER_ENCODING,
+};
 use hyper::{Method, Request as HyperRequest, Response as HyperResponse, Uri};
+use socket2::{SockRef, TcpKeepalive};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
 use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
-use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::net::TcpStream;
+use tokio_native_tls::TlsStream;
+use tracing::{debug, error, instrument, warn};
+
+/// Default size of each chunk read from disk when streaming a file body
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Smallest read buffer limit hyper accepts for HTTP/1
+pub const MIN_BUFFER_SIZE: usize = 8192;
+
+/// Request body type sent through hyper: buffered bytes or a streamed file
+type RequestBody = UnsyncBoxBody<Bytes, std::io::Error>;
+
+#[cfg(feature = "http3")]
+mod http3;
+
+/// Default cap on the number of response headers
+pub const DEFAULT_MAX_HEADERS: usize = 100;
+
+/// Delay before the first connect retry; doubled on each further attempt up to `MAX_RETRY_DELAY`
+const CONNECT_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Form of the request-target written on the HTTP/1 request line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RequestTarget {
+    /// `/path?query`, used for direct requests to an origin server
+    #[default]
+    Origin,
+    /// `http://host/path`, used for requests to a forward proxy
+    Absolute,
+    /// `host:port`, used for CONNECT
+    Authority,
+    /// `*`, used for server-wide OPTIONS
+    Asterisk,
+}
+
+impl FromStr for RequestTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "origin" => Ok(RequestTarget::Origin),
+            "absolute" => Ok(RequestTarget::Absolute),
+            "authority" => Ok(RequestTarget::Authority),
+            "asterisk" => Ok(RequestTarget::Asterisk),
+            other => Err(anyhow!(
+                "Unsupported request target: '{}'. Use origin, absolute, authority or asterisk",
+                other
+            )),
+        }
+    }
+}
+
+/// Transport settings applied to every connection and response
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Maximum number of response headers to accept
+    pub max_headers: usize,
+    /// Form of the request-target on the request line
+    pub request_target: RequestTarget,
+    /// Drain and drop the response body instead of buffering it
+    pub discard_body: bool,
+    /// Disable Nagle's algorithm so small writes go out immediately
+    pub tcp_nodelay: bool,
+    /// Idle time before TCP keepalive probes are sent; `None` leaves keepalive off
+    pub keepalive_time: Option<Duration>,
+    /// Write header names in Title-Case instead of lowercase
+    pub title_case_headers: bool,
+    /// Extra attempts at establishing the connection when TCP/TLS connect fails
+    pub connect_retries: u32,
+    /// File body chunk size and, when given, the limit on hyper's read buffer
+    /// (raised to `MIN_BUFFER_SIZE` if smaller)
+    pub buffer_size: Option<usize>,
+    /// Server name sent in the TLS handshake instead of the URL host
+    pub sni: Option<String>,
+    /// On a failed TLS handshake, fetch and describe the certificate the server presented
+    pub show_cert_on_error: bool,
+    /// Add a Host header from the URL authority
+    pub host_header: bool,
+    /// Header names to send first, in this order, ahead of the remaining headers
+    pub header_order: Vec<String>,
+    /// Gzip the request body on the fly and send it with `Transfer-Encoding: gzip, chunked`
+    pub transfer_gzip: bool,
+    /// Send the request body chunked, in chunks of exactly this many bytes (the last may be shorter)
+    pub chunk_size: Option<usize>,
+    /// Headers sent after a chunked request body, announced in a `Trailer` header;
+    /// requests without a body are sent without them
+    pub trailers: Vec<(String, String)>,
+    /// Records the raw bytes exchanged on every connection
+    pub transcript: Option<TranscriptRecorder>,
+    /// Serves responses from a recorded transcript instead of the network
+    pub replay: Option<Arc<WireTranscript>>,
+    /// Replays recorded chunks with their original timing divided by this factor;
+    /// `None` sends them immediately
+    pub replay_speed: Option<f64>,
+    /// Write the request's own header names exactly as they were given
+    pub header_case_sensitive: bool,
+    /// Send requests over HTTP/3 (QUIC) instead of HTTP/1.1; needs the `http3` feature
+    pub http3: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            max_headers: DEFAULT_MAX_HEADERS,
+            request_target: RequestTarget::default(),
+            discard_body: false,
+            tcp_nodelay: true,
+            keepalive_time: None,
+            title_case_headers: false,
+            connect_retries: 0,
+            buffer_size: None,
+            sni: None,
+            show_cert_on_error: false,
+            host_header: true,
+            header_order: Vec::new(),
+            transfer_gzip: false,
+            chunk_size: None,
+            trailers: Vec::new(),
+            transcript: None,
+            replay: None,
+            replay_speed: None,
+            header_case_sensitive: false,
+            http3: false,
+        }
+    }
+}
+
+// Turns the common native-tls verification failures into actionable messages
+fn tls_handshake_error(err: &impl std::fmt::Display, server_name: &str) -> anyhow::Error {
+    let message = err.to_string();
+    let lower = message.to_lowercase();
+
+    let hint = if lower.contains("certificate has expired") || lower.contains("not yet valid") {
+        "the server's certificate is expired or not yet valid (check the server certificate and the local clock)".to_string()
+    } else if lower.contains("hostname mismatch") || lower.contains("ip address mismatch") {
+        format!(
+            "the server's certificate is not valid for '{}' (use --sni to verify against the name it was issued for)",
+            server_name
+        )
+    } else if lower.contains("self-signed")
+        || lower.contains("self signed")
+        || lower.contains("unable to get local issuer")
+        || lower.contains("untrusted root")
+    {
+        "the server's certificate is not signed by a trusted root (add the issuing CA to the system trust store)".to_string()
+    } else if lower.contains("wrong version number") {
+        "the server did not answer with TLS (is it plain HTTP? try an http:// URL)".to_string()
+    } else if lower.contains("protocol version") || lower.contains("unsupported protocol") {
+        "the client and server have no TLS protocol version in common".to_string()
+    } else {
+        return anyhow!("TLS handshake failed: {}", message);
+    };
+
+    anyhow!("TLS handshake failed: {}: {}", hint, message)
+}
+
+// Subject, issuer, validity and alternative names of a DER certificate
+fn describe_certificate(der: &[u8]) -> Result<String> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| anyhow!("Failed to parse the certificate: {}", e))?;
+    let validity = certificate.validity();
+
+    let mut lines = vec![
+        format!("  subject: {}", certificate.subject()),
+        format!("  issuer: {}", certificate.issuer()),
+        format!("  valid from: {}", validity.not_before),
+        format!("  valid until: {}", validity.not_after),
+    ];
+    if let Ok(Some(names)) = certificate.subject_alternative_name() {
+        let names: Vec<String> = names
+            .value
+            .general_names
+            .iter()
+            .map(alternative_name)
+            .collect();
+        lines.push(format!("  alternative names: {}", names.join(", ")));
+    }
+    Ok(lines.join("\n"))
+}
+
+// x509-parser prints IP addresses as raw hex bytes
+fn alternative_name(name: &x509_parser::extensions::GeneralName) -> String {
+    use x509_parser::extensions::GeneralName;
+    let ip = match name {
+        GeneralName::IPAddress(bytes) => <[u8; 4]>::try_from(*bytes)
+            .map(std::net::IpAddr::from)
+            .or_else(|_| <[u8; 16]>::try_from(*bytes).map(std::net::IpAddr::from))
+            .ok(),
+        _ => None,
+    };
+    match ip {
+        Some(ip) => format!("IPAddress({})", ip),
+        None => name.to_string(),
+    }
+}
+
+// Host and `host:port` to dial, with the port defaulting from the scheme
+fn target_addr(uri: &Uri) -> Result<(&str, String)> {
+    let host = uri.host().ok_or_else(|| anyhow!("No host in URI"))?;
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("https") {
+            443
+        } else {
+            80
+        });
+    Ok((host, format!("{}:{}", host, port)))
+}
+
+impl ClientOptions {
+    /// The buffer size actually used, with values below `MIN_BUFFER_SIZE` raised to it
+    fn buffer_size(&self) -> Option<usize> {
+        self.buffer_size.map(|size| size.max(MIN_BUFFER_SIZE))
+    }
+}
 
 /// HTTP client using Hyper without hyper-util
-pub struct HyperHttpClient;
+pub struct HyperHttpClient {
+    options: ClientOptions,
+    /// Recorded connections already served by `replay_connection`
+    replayed: AtomicUsize,
+}
 
 impl HyperHttpClient {
-    pub fn new() -> Self {
-        Self
+    pub fn new(options: ClientOptions) -> Self {
+        Self {
+            options,
+            replayed: AtomicUsize::new(0),
+        }
     }
 
-    async fn create_connection(&self, uri: &Uri) -> Result<Box<dyn Connection>> {
-        let host = uri.host().ok_or_else(|| anyhow!("No host in URI"))?;
-        let port = uri
-            .port_u16()
-            .unwrap_or(if uri.scheme_str() == Some("https") {
-                443
-            } else {
-                80
-            });
-        let addr = format!("{}:{}", host, port);
+    // Serves the recorded response bytes over an in-memory pipe so the request
+    // and response still go through hyper exactly as they would on the wire.
+    // Each new connection replays the next recorded one, so redirects and
+    // retries get the responses they got when the transcript was made.
+    async fn replay_connection(
+        &self,
+        transcript: &WireTranscript,
+        casing: Option<HeaderCasing>,
+    ) -> Result<Box<dyn Connection>> {
+        let connection = self.replayed.fetch_add(1, Ordering::Relaxed);
+        let recorded = transcript.connection_count();
+        if connection >= recorded {
+            return Err(anyhow!(
+                "The transcript has no more connections to replay ({} recorded)",
+                recorded
+            ));
+        }
+        let chunks = transcript.received_chunks(connection)?;
+        let speed = self.options.replay_speed;
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (mut server_read, mut server_write) = tokio::io::split(server_io);
 
-        if uri.scheme_str() == Some("https") {
-            let stream = TcpStream::connect(&addr)
-                .await
-                .map_err(|e| anyhow!("Failed to connect to {}: {}", addr, e))?;
+        let (request_seen, request_arrived) = tokio::sync::oneshot::channel();
 
-            let connector = tokio_native_tls::native_tls::TlsConnector::new()
-                .map_err(|e| anyhow!("Failed to create TLS connector: {}", e))?;
-            let connector = tokio_native_tls::TlsConnector::from(connector);
+        // hyper rejects a response that arrives before its request was written,
+        // so hold the recorded bytes back until the request starts coming in
+        tokio::task::spawn(async move {
+            let mut request_seen = Some(request_seen);
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = server_read.read(&mut buf).await {
+                if n == 0 {
+                    break;
+                }
+                if let Some(tx) = request_seen.take() {
+                    let _ = tx.send(());
+                }
+            }
+        });
+        tokio::task::spawn(async move {
+            if request_arrived.await.is_err() {
+                return;
+            }
+            // With a replay speed, each chunk waits until its recorded offset
+            // (scaled by the speed) after the request started
+            let started = tokio::time::Instant::now();
+            for (offset, chunk) in chunks {
+                if let Some(speed) = speed {
+                    tokio::time::sleep_until(started + offset.div_f64(speed)).await;
+                }
+                if let Err(err) = server_write.write_all(&chunk).await {
+                    error!(error = ?err, "Transcript replay failed");
+                    return;
+                }
+            }
+            let _ = server_write.shutdown().await;
+        });
 
-            let tls_stream = connector
-                .connect(host, stream)
-                .await
-                .map_err(|e| anyhow!("TLS handshake failed: {}", e))?;
+        let io = TokioIoAdapter::new(client_io, self.options.transcript.clone(), casing);
+        let (sender, conn) = self
+            .http1_builder()
+            .handshake(io)
+            .await
+            .map_err(|e| anyhow!("HTTP handshake failed: {}", e))?;
+
+        tokio::task::spawn(async move {
+            if let Err(err) = conn.await {
+                error!(error = ?err, "Connection failed");
+            }
+        });
 
-            let io = TokioIoAdapter::new(tls_stream);
-            let (sender, conn) = hyper::client::conn::http1::handshake(io)
+        Ok(Box::new(HttpConnection { sender }))
+    }
+
+    // The SNI name is also the name native-tls verifies the certificate against
+    async fn tls_handshake(
+        &self,
+        host: &str,
+        stream: TcpStream,
+        alpn: &[&str],
+    ) -> Result<TlsStream<HelloCapture<TcpStream>>> {
+        let connector = tokio_native_tls::native_tls::TlsConnector::builder()
+            .request_alpns(alpn)
+            .build()
+            .map_err(|e| anyhow!("Failed to create TLS connector: {}", e))?;
+        let connector = tokio_native_tls::TlsConnector::from(connector);
+
+        let server_name = self.options.sni.as_deref().unwrap_or(host);
+        let tls_stream = connector
+            .connect(server_name, HelloCapture::new(stream))
+            .await
+            .map_err(|e| tls_handshake_error(&e, server_name))?;
+        debug!(server_name, "TLS handshake complete");
+        Ok(tls_stream)
+    }
+
+    // Appends the certificate the server presented to a failed handshake's error
+    async fn with_presented_certificate(
+        &self,
+        err: anyhow::Error,
+        host: &str,
+        addr: &str,
+    ) -> anyhow::Error {
+        match self.presented_certificate(host, addr).await {
+            Ok(details) => anyhow!("{}\nServer certificate:\n{}", err, details),
+            Err(probe_err) => anyhow!(
+                "{}\n(could not retrieve the server certificate: {})",
+                err,
+                probe_err
+            ),
+        }
+    }
+
+    // Repeats the handshake with verification off, only to read the peer's
+    // certificate; the connection is dropped right after. native-tls exposes
+    // the leaf certificate but not the rest of the chain.
+    async fn presented_certificate(&self, host: &str, addr: &str) -> Result<String> {
+        let stream = self.connect_tcp(addr).await?;
+        let connector = tokio_native_tls::native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .map_err(|e| anyhow!("Failed to create TLS connector: {}", e))?;
+        let server_name = self.options.sni.as_deref().unwrap_or(host);
+        let tls_stream = tokio_native_tls::TlsConnector::from(connector)
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| anyhow!("TLS handshake failed: {}", e))?;
+
+        let certificate = tls_stream
+            .get_ref()
+            .peer_certificate()
+            .map_err(|e| anyhow!("Failed to read the certificate: {}", e))?
+            .ok_or_else(|| anyhow!("The server sent no certificate"))?;
+        let der = certificate
+            .to_der()
+            .map_err(|e| anyhow!("Failed to encode the certificate: {}", e))?;
+        describe_certificate(&der)
+    }
+
+    async fn connect_tcp(&self, addr: &str) -> Result<TcpStream> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to {}: {}", addr, e))?;
+        debug!(%addr, "TCP connection established");
+
+        SocketConfig::from_options(&self.options).apply(&stream)?;
+        Ok(stream)
+    }
+
+    fn http1_builder(&self) -> hyper::client::conn::http1::Builder {
+        let mut builder = hyper::client::conn::http1::Builder::new();
+        // Never parse fewer headers than hyper's default, and leave room for one
+        // extra header so ResponseAdapter can report the overflow itself
+        builder.max_headers(
+            self.options
+                .max_headers
+                .max(DEFAULT_MAX_HEADERS)
+                .saturating_add(1),
+        );
+        // Names given with --header-case-sensitive are respelled afterwards by
+        // HeaderCasing, on top of either casing
+        builder.title_case_headers(self.options.title_case_headers);
+        // A ceiling rather than an exact size, so a header line longer than
+        // the buffer still parses as long as it fits under the limit
+        if let Some(size) = self.options.buffer_size() {
+            builder.max_buf_size(size);
+        }
+        builder
+    }
+
+    // Connects, sends the request and returns the response head with the
+    // time it took to connect and the TLS session, if any
+    async fn exchange(
+        &self,
+        request: Request,
+        uri: &Uri,
+    ) -> Result<(
+        HyperResponse<hyper::body::Incoming>,
+        Duration,
+        Option<TlsInfo>,
+    )> {
+        let started = Instant::now();
+        let casing = self
+            .options
+            .header_case_sensitive
+            .then(|| HeaderCasing::new(&request.headers));
+        let mut conn = self.connect_with_retries(uri, casing).await?;
+        let connect = started.elapsed();
+        let hyper_request = RequestAdapter::to_hyper_request(request, uri, &self.options)?;
+        let hyper_response = conn.send_request(hyper_request).await?;
+        debug!(status = %hyper_response.status(), "Received response");
+        Ok((hyper_response, connect, conn.tls()))
+    }
+
+    async fn connect_with_retries(
+        &self,
+        uri: &Uri,
+        casing: Option<HeaderCasing>,
+    ) -> Result<Box<dyn Connection>> {
+        retry_connect(self.options.connect_retries, || {
+            self.create_connection(uri, casing.clone())
+        })
+        .await
+    }
+
+    #[cfg(feature = "http3")]
+    async fn send_http3(
+        &self,
+        request: Request,
+        on_chunk: Option<&mut (dyn FnMut(Bytes) + Send)>,
+    ) -> Result<Response> {
+        http3::send(request, &self.options, on_chunk).await
+    }
+
+    #[cfg(not(feature = "http3"))]
+    async fn send_http3(
+        &self,
+        _request: Request,
+        _on_chunk: Option<&mut (dyn FnMut(Bytes) + Send)>,
+    ) -> Result<Response> {
+        Err(anyhow!(
+            "HTTP/3 support is not enabled; rebuild Hurl with --features http3"
+        ))
+    }
+
+    #[instrument(name = "connect", skip(self, casing), fields(uri = %uri))]
+    async fn create_connection(
+        &self,
+        uri: &Uri,
+        casing: Option<HeaderCasing>,
+    ) -> Result<Box<dyn Connection>> {
+        if let Some(transcript) = &self.options.replay {
+            return self.replay_connection(transcript, casing).await;
+        }
+
+        let (host, addr) = target_addr(uri)?;
+
+        if uri.scheme_str() == Some("https") {
+            let stream = self.connect_tcp(&addr).await?;
+            // Offering http/1.1, the only protocol spoken here, lets the server confirm it
+            let tls_stream = match self.tls_handshake(host, stream, &["http/1.1"]).await {
+                Ok(tls_stream) => tls_stream,
+                Err(err) if self.options.show_cert_on_error => {
+                    return Err(self.with_presented_certificate(err, host, &addr).await);
+                }
+                Err(err) => return Err(err),
+            };
+            let tls = session_info(&tls_stream, self.options.sni.as_deref().unwrap_or(host))?;
+
+            let io = TokioIoAdapter::new(tls_stream, self.options.transcript.clone(), casing);
+            let (sender, conn) = self
+                .http1_builder()
+                .handshake(io)
                 .await
                 .map_err(|e| anyhow!("HTTP handshake failed: {}", e))?;
 
             tokio::task::spawn(async move {
                 if let Err(err) = conn.await {
-                    eprintln!("Connection failed: {:?}", err);
+                    error!(error = ?err, "Connection failed");
                 }
             });
 
-            Ok(Box::new(HttpsConnection { sender }))
+            Ok(Box::new(HttpsConnection { sender, tls }))
         } else {
-            let stream = TcpStream::connect(&addr)
-                .await
-                .map_err(|e| anyhow!("Failed to connect to {}: {}", addr, e))?;
+            let stream = self.connect_tcp(&addr).await?;
 
-            let io = TokioIoAdapter::new(stream);
-            let (sender, conn) = hyper::client::conn::http1::handshake(io)
+            let io = TokioIoAdapter::new(stream, self.options.transcript.clone(), casing);
+            let (sender, conn) = self
+                .http1_builder()
+                .handshake(io)
                 .await
                 .map_err(|e| anyhow!("HTTP handshake failed: {}", e))?;
 
             tokio::task::spawn(async move {
                 if let Err(err) = conn.await {
-                    eprintln!("Connection failed: {:?}", err);
+                    error!(error = ?err, "Connection failed");
                 }
             });
 
@@ -78,14 +545,257 @@ impl HyperHttpClient {
     }
 }
 
-// Simple adapter that implements hyper::rt traits for tokio IO types
+// Socket-level options applied to every TCP stream before the HTTP handshake
+#[derive(Debug, Clone, Copy)]
+struct SocketConfig {
+    keepalive_time: Option<Duration>,
+    nodelay: bool,
+}
+
+impl SocketConfig {
+    fn from_options(options: &ClientOptions) -> Self {
+        Self {
+            keepalive_time: options.keepalive_time,
+            nodelay: options.tcp_nodelay,
+        }
+    }
+
+    fn apply(&self, stream: &TcpStream) -> Result<()> {
+        let socket = SockRef::from(stream);
+
+        socket
+            .set_nodelay(self.nodelay)
+            .map_err(|e| anyhow!("Failed to set TCP_NODELAY: {}", e))?;
+        debug!(nodelay = self.nodelay, "TCP_NODELAY configured");
+
+        if let Some(time) = self.keepalive_time {
+            socket
+                .set_tcp_keepalive(&TcpKeepalive::new().with_time(time))
+                .map_err(|e| anyhow!("Failed to enable TCP keepalive: {}", e))?;
+            debug!(seconds = time.as_secs(), "TCP keepalive enabled");
+        }
+        Ok(())
+    }
+}
+
+// native-tls reports neither the TLS version nor the cipher suite, but both
+// are in the server's first handshake record, the ServerHello, which is sent
+// in the clear. This keeps the bytes read until that record is complete.
+struct HelloCapture<S> {
+    inner: S,
+    received: Vec<u8>,
+}
+
+impl<S> HelloCapture<S> {
+    /// Largest TLS record, plus its header
+    const MAX_RECORD: usize = 5 + (1 << 14);
+
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            received: Vec::new(),
+        }
+    }
+
+    fn wants(&self) -> usize {
+        let length = match self.received.get(3..5) {
+            Some(length) => 5 + u16::from_be_bytes([length[0], length[1]]) as usize,
+            None => 5,
+        };
+        length
+            .min(Self::MAX_RECORD)
+            .saturating_sub(self.received.len())
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for HelloCapture<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            // The header says how long the record is, so a read holding both
+            // takes two steps
+            let mut read = &buf.filled()[before..];
+            while let take @ 1.. = self.wants().min(read.len()) {
+                self.received.extend_from_slice(&read[..take]);
+                read = &read[take..];
+            }
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for HelloCapture<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+// Simple adapter that implements hyper::rt traits for tokio IO types,
+// optionally recording every chunk read or written to a transcript
 struct TokioIoAdapter<T> {
     inner: T,
+    /// The recorder and the number it gave this connection
+    recorder: Option<(TranscriptRecorder, usize)>,
+    /// Header spellings to restore in the request head
+    casing: Option<HeaderCasing>,
 }
 
 impl<T> TokioIoAdapter<T> {
-    fn new(inner: T) -> Self {
-        Self { inner }
+    fn new(inner: T, recorder: Option<TranscriptRecorder>, casing: Option<HeaderCasing>) -> Self {
+        let recorder = recorder.map(|recorder| {
+            let connection = recorder.open_connection();
+            (recorder, connection)
+        });
+        Self {
+            inner,
+            recorder,
+            casing,
+        }
+    }
+}
+
+/// Puts header names in an outgoing HTTP/1 request head back the way they
+/// were typed. hyper keeps its own original-case map private and writes names
+/// lowercase (or Title-Case), so the spelling is restored on the way out; only
+/// the case of each letter changes, never the length.
+#[derive(Debug, Clone)]
+struct HeaderCasing {
+    /// Spellings still to be written, by lowercase name, in the order given
+    names: HashMap<String, VecDeque<String>>,
+    /// Where in the head the next byte written falls
+    position: HeadPosition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum HeadPosition {
+    RequestLine,
+    LineStart,
+    /// Inside a header name, writing `spelling` when it is known
+    Name {
+        spelling: Option<Vec<u8>>,
+        at: usize,
+    },
+    Value,
+    /// After the `\r` of the blank line that ends the head
+    BlankLine,
+    Body,
+}
+
+impl HeaderCasing {
+    fn new(headers: &[(String, String)]) -> Self {
+        let mut names: HashMap<String, VecDeque<String>> = HashMap::new();
+        for (name, _) in headers {
+            names
+                .entry(name.to_ascii_lowercase())
+                .or_default()
+                .push_back(name.clone());
+        }
+        Self {
+            names,
+            position: HeadPosition::RequestLine,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.position == HeadPosition::Body
+    }
+
+    // Rewrites as much of the first `limit` bytes of `buf` as can be decided
+    // now. A header name that continues past the end of `buf` is left for the
+    // next write, unless nothing before it could be written.
+    fn rewrite(&mut self, buf: &[u8], limit: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(limit);
+        for (i, &byte) in buf[..limit].iter().enumerate() {
+            let position = std::mem::replace(&mut self.position, HeadPosition::Body);
+            self.position = match position {
+                HeadPosition::RequestLine | HeadPosition::Value => {
+                    out.push(byte);
+                    if byte == b'\n' {
+                        HeadPosition::LineStart
+                    } else {
+                        position
+                    }
+                }
+                HeadPosition::LineStart if byte == b'\r' => {
+                    out.push(byte);
+                    HeadPosition::BlankLine
+                }
+                HeadPosition::LineStart => {
+                    let Some(len) = buf[i..].iter().position(|&b| b == b':') else {
+                        if i > 0 {
+                            self.position = HeadPosition::LineStart;
+                            return out;
+                        }
+                        out.extend_from_slice(&buf[..limit]);
+                        self.position = HeadPosition::Value;
+                        return out;
+                    };
+                    let spelling = self.spelling(&buf[i..i + len]);
+                    Self::name_byte(&mut out, byte, spelling, 0)
+                }
+                HeadPosition::Name { spelling, at } => {
+                    Self::name_byte(&mut out, byte, spelling, at)
+                }
+                HeadPosition::BlankLine => {
+                    out.push(byte);
+                    HeadPosition::Body
+                }
+                HeadPosition::Body => {
+                    out.extend_from_slice(&buf[i..limit]);
+                    return out;
+                }
+            };
+        }
+        out
+    }
+
+    // The next typed spelling of `name`, if it differs from what hyper wrote only in case
+    fn spelling(&mut self, name: &[u8]) -> Option<Vec<u8>> {
+        let name = std::str::from_utf8(name).ok()?;
+        let spellings = self.names.get_mut(&name.to_ascii_lowercase())?;
+        let spelling = spellings.pop_front()?;
+        spelling
+            .eq_ignore_ascii_case(name)
+            .then(|| spelling.into_bytes())
+    }
+
+    fn name_byte(
+        out: &mut Vec<u8>,
+        byte: u8,
+        spelling: Option<Vec<u8>>,
+        at: usize,
+    ) -> HeadPosition {
+        if byte == b':' {
+            out.push(byte);
+            return HeadPosition::Value;
+        }
+        out.push(
+            spelling
+                .as_ref()
+                .and_then(|s| s.get(at).copied())
+                .unwrap_or(byte),
+        );
+        HeadPosition::Name {
+            spelling,
+            at: at + 1,
+        }
     }
 }
 
@@ -98,10 +808,16 @@ where
         cx: &mut Context<'_>,
         mut buf: hyper::rt::ReadBufCursor<'_>,
     ) -> Poll<Result<(), std::io::Error>> {
+        let this = &mut *self;
         let n = unsafe {
             let mut tbuf = ReadBuf::uninit(buf.as_mut());
-            match AsyncRead::poll_read(Pin::new(&mut self.inner), cx, &mut tbuf) {
-                Poll::Ready(Ok(())) => tbuf.filled().len(),
+            match AsyncRead::poll_read(Pin::new(&mut this.inner), cx, &mut tbuf) {
+                Poll::Ready(Ok(())) => {
+                    if let Some((recorder, connection)) = &this.recorder {
+                        recorder.record(*connection, Direction::Received, tbuf.filled());
+                    }
+                    tbuf.filled().len()
+                }
                 other => return other,
             }
         };
@@ -122,7 +838,31 @@ where
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, std::io::Error>> {
-        AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+        let this = &mut *self;
+        let Some(casing) = this.casing.as_mut().filter(|casing| !casing.is_done()) else {
+            let poll = AsyncWrite::poll_write(Pin::new(&mut this.inner), cx, buf);
+            if let (Poll::Ready(Ok(n)), Some((recorder, connection))) = (&poll, &this.recorder) {
+                recorder.record(*connection, Direction::Sent, &buf[..*n]);
+            }
+            return poll;
+        };
+
+        // Work on a copy so the position only moves past the bytes the socket
+        // took; a rewrite decided for bytes it did not take is redone next time
+        let mut attempt = casing.clone();
+        let rewritten = attempt.rewrite(buf, buf.len());
+        let poll = AsyncWrite::poll_write(Pin::new(&mut this.inner), cx, &rewritten);
+        if let Poll::Ready(Ok(n)) = &poll {
+            if *n == rewritten.len() {
+                *casing = attempt;
+            } else {
+                casing.rewrite(buf, *n);
+            }
+            if let Some((recorder, connection)) = &this.recorder {
+                recorder.record(*connection, Direction::Sent, &rewritten[..*n]);
+            }
+        }
+        poll
     }
 
     fn poll_flush(
@@ -145,19 +885,24 @@ where
 trait Connection: Send {
     async fn send_request(
         &mut self,
-        req: HyperRequest<Full<Bytes>>,
+        req: HyperRequest<RequestBody>,
     ) -> Result<HyperResponse<hyper::body::Incoming>>;
+
+    /// The negotiated TLS session, for connections that have one
+    fn tls(&self) -> Option<TlsInfo> {
+        None
+    }
 }
 
 struct HttpConnection {
-    sender: hyper::client::conn::http1::SendRequest<Full<Bytes>>,
+    sender: hyper::client::conn::http1::SendRequest<RequestBody>,
 }
 
 #[async_trait]
 impl Connection for HttpConnection {
     async fn send_request(
         &mut self,
-        req: HyperRequest<Full<Bytes>>,
+        req: HyperRequest<RequestBody>,
     ) -> Result<HyperResponse<hyper::body::Incoming>> {
         self.sender
             .send_request(req)
@@ -167,32 +912,154 @@ impl Connection for HttpConnection {
 }
 
 struct HttpsConnection {
-    sender: hyper::client::conn::http1::SendRequest<Full<Bytes>>,
+    sender: hyper::client::conn::http1::SendRequest<RequestBody>,
+    tls: TlsInfo,
 }
 
 #[async_trait]
 impl Connection for HttpsConnection {
     async fn send_request(
         &mut self,
-        req: HyperRequest<Full<Bytes>>,
+        req: HyperRequest<RequestBody>,
     ) -> Result<HyperResponse<hyper::body::Incoming>> {
         self.sender
             .send_request(req)
             .await
             .map_err(|e| anyhow!("Failed to send HTTPS request: {}", e))
     }
+
+    fn tls(&self) -> Option<TlsInfo> {
+        Some(self.tls.clone())
+    }
 }
 
 #[async_trait]
 impl crate::application::services::HttpClient for HyperHttpClient {
+    #[instrument(
+        name = "send",
+        skip_all,
+        fields(method = ?request.method, url = %request.url.as_str())
+    )]
     async fn send(&self, request: Request) -> Result<Response> {
+        if self.options.http3 {
+            return self.send_http3(request, None).await;
+        }
         let uri = request.url.0.clone();
+        let started = Instant::now();
 
-        let mut conn = self.create_connection(&uri).await?;
-        let hyper_request = RequestAdapter::to_hyper_request(request, &uri)?;
-        let hyper_response = conn.send_request(hyper_request).await?;
+        let (hyper_response, connect, tls) = self.exchange(request, &uri).await?;
+        let mut response =
+            ResponseAdapter::to_domain_response(hyper_response, &self.options).await?;
+        response.timing = Timing {
+            connect,
+            total: started.elapsed(),
+        };
+        response.tls = tls;
+        Ok(response)
+    }
+
+    #[instrument(
+        name = "send",
+        skip_all,
+        fields(method = ?request.method, url = %request.url.as_str())
+    )]
+    async fn send_streaming(
+        &self,
+        request: Request,
+        on_chunk: &mut (dyn FnMut(Bytes) + Send),
+    ) -> Result<Response> {
+        if self.options.http3 {
+            return self.send_http3(request, Some(on_chunk)).await;
+        }
+        let uri = request.url.0.clone();
+        let started = Instant::now();
+
+        let (hyper_response, connect, tls) = self.exchange(request, &uri).await?;
+        let mut response =
+            ResponseAdapter::to_streamed_response(hyper_response, &self.options, on_chunk).await?;
+        response.timing = Timing {
+            connect,
+            total: started.elapsed(),
+        };
+        response.tls = tls;
+        Ok(response)
+    }
+
+    // Offers h2 and http/1.1 over ALPN so the report shows what the server
+    // prefers, even though requests themselves are sent over HTTP/1.1
+    #[instrument(name = "connect", skip(self), fields(url = %url.as_str()))]
+    async fn connect(&self, url: &Url) -> Result<ConnectionInfo> {
+        if self.options.replay.is_some() {
+            return Err(anyhow!(
+                "--connect-only needs a real connection, not a replayed transcript"
+            ));
+        }
+
+        let uri = &url.0;
+        let started = Instant::now();
+        let (host, addr) = target_addr(uri)?;
+        let stream = self.connect_tcp(&addr).await?;
+        let peer = stream
+            .peer_addr()
+            .map(|peer| peer.to_string())
+            .unwrap_or(addr);
+
+        let tls = if uri.scheme_str() == Some("https") {
+            let tls_stream = self
+                .tls_handshake(host, stream, &["h2", "http/1.1"])
+                .await?;
+            Some(session_info(
+                &tls_stream,
+                self.options.sni.as_deref().unwrap_or(host),
+            )?)
+        } else {
+            None
+        };
+
+        Ok(ConnectionInfo {
+            peer,
+            tls,
+            elapsed: started.elapsed(),
+        })
+    }
+}
 
-        ResponseAdapter::to_domain_response(hyper_response).await
+/// Returns the request headers exactly as they will be sent, including the
+/// ones Hurl adds automatically
+pub fn resolved_headers(
+    request: &Request,
+    header_order: &[String],
+    host_header: bool,
+) -> Vec<(String, String)> {
+    let mut headers = HeaderAdapter::resolve(request, &request.url.0, host_header);
+    HeaderAdapter::reorder(&mut headers, header_order);
+    headers
+}
+
+// Calls `connect` until it succeeds or has failed `retries + 1` times,
+// backing off between attempts
+async fn retry_connect<T, F, Fut>(retries: u32, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match connect().await {
+            Ok(conn) => return Ok(conn),
+            Err(err) if attempt < retries => {
+                let delay = backoff_delay(CONNECT_RETRY_BASE_DELAY, attempt);
+                attempt += 1;
+                warn!(
+                    error = %err,
+                    attempt,
+                    delay_ms = delay.as_millis() as u64,
+                    "Connect failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(err) => return Err(err),
+        }
     }
 }
 
@@ -200,24 +1067,74 @@ impl crate::application::services::HttpClient for HyperHttpClient {
 struct RequestAdapter;
 
 impl RequestAdapter {
-    fn to_hyper_request(domain_request: Request, uri: &Uri) -> Result<HyperRequest<Full<Bytes>>> {
+    fn to_hyper_request(
+        domain_request: Request,
+        uri: &Uri,
+        options: &ClientOptions,
+    ) -> Result<HyperRequest<RequestBody>> {
+        let mut headers = HeaderAdapter::resolve(&domain_request, uri, options.host_header);
         let method = MethodAdapter::to_hyper_method(domain_request.method);
-        let body = BodyAdapter::to_hyper_body(&domain_request.body);
-
-        let mut builder = HyperRequest::builder().method(method).uri(uri);
-
-        // Add HOST header as required by hyper
-        if let Some(authority) = uri.authority() {
-            builder = builder.header(HOST, authority.as_str());
+        let chunk_size = options.buffer_size().unwrap_or(DEFAULT_BUFFER_SIZE);
+        let mut body = BodyAdapter::to_hyper_body(&domain_request.body, chunk_size)?;
+        if options.transfer_gzip && domain_request.body.is_some() {
+            HeaderAdapter::gzip_transfer_coding(&mut headers);
+            body = BodyAdapter::gzip_body(body);
         }
+        // Applied after gzip so the sizes hold for the chunks on the wire
+        if let Some(size) = options.chunk_size
+            && domain_request.body.is_some()
+        {
+            HeaderAdapter::chunked_transfer_coding(&mut headers);
+            body = BodyAdapter::rechunk(body, size);
+        }
+        // Last, since the other adapters pass on only data frames
+        if !options.trailers.is_empty() && domain_request.body.is_some() {
+            HeaderAdapter::chunked_transfer_coding(&mut headers);
+            HeaderAdapter::announce_trailers(&mut headers, &options.trailers);
+            body = BodyAdapter::with_trailers(body, Self::trailer_map(&options.trailers)?);
+        }
+        HeaderAdapter::reorder(&mut headers, &options.header_order);
+        let request_target = Self::request_target(uri, options.request_target)?;
 
-        builder = HeaderAdapter::add_json_content_type(builder, &domain_request.body);
-        builder = HeaderAdapter::add_headers(builder, &domain_request.headers);
+        let mut builder = HyperRequest::builder().method(method).uri(request_target);
+        builder = HeaderAdapter::add_headers(builder, &headers);
 
         builder
             .body(body)
             .map_err(|e| anyhow!("Failed to build HTTP request: {}", e))
     }
+
+    fn trailer_map(trailers: &[(String, String)]) -> Result<HeaderMap> {
+        let mut map = HeaderMap::new();
+        for (name, value) in trailers {
+            let name = HeaderName::from_str(name)
+                .map_err(|e| anyhow!("Invalid trailer name '{}': {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| anyhow!("Invalid value for trailer '{}': {}", name, e))?;
+            map.append(name, value);
+        }
+        Ok(map)
+    }
+
+    fn request_target(uri: &Uri, target: RequestTarget) -> Result<Uri> {
+        let target = match target {
+            RequestTarget::Origin => uri
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/")
+                .to_string(),
+            RequestTarget::Absolute => return Ok(uri.clone()),
+            RequestTarget::Authority => uri
+                .authority()
+                .ok_or_else(|| anyhow!("Authority-form requires a host in the URL"))?
+                .to_string(),
+            RequestTarget::Asterisk => "*".to_string(),
+        };
+
+        target
+            .parse::<Uri>()
+            .map_err(|e| anyhow!("Invalid request target '{}': {}", target, e))
+    }
 }
 
 // Adapter to convert Hyper Response to domain Response
@@ -226,20 +1143,193 @@ struct ResponseAdapter;
 impl ResponseAdapter {
     async fn to_domain_response(
         hyper_response: HyperResponse<hyper::body::Incoming>,
+        options: &ClientOptions,
     ) -> Result<Response> {
         let status = hyper_response.status();
-        let body_bytes = hyper_response
+        let headers = Self::collect_headers(hyper_response.headers(), options.max_headers)?;
+
+        if options.discard_body {
+            let trailers = Self::stream_body(hyper_response.into_body(), &mut |_| {}).await?;
+            return Ok(Response {
+                status,
+                headers,
+                body_bytes: Vec::new(),
+                trailers,
+                timing: Timing::default(),
+                tls: None,
+            });
+        }
+
+        let collected = hyper_response
             .into_body()
             .collect()
             .await
-            .map_err(|e| anyhow!("Failed to read response body: {}", e))?
-            .to_bytes();
+            .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+        let trailers = collected.trailers().map(header_pairs).unwrap_or_default();
 
-        let body = String::from_utf8(body_bytes.to_vec())
-            .map_err(|e| anyhow!("Invalid UTF-8 in response body: {}", e))?;
+        Ok(Response {
+            status,
+            headers,
+            body_bytes: collected.to_bytes().to_vec(),
+            trailers,
+            timing: Timing::default(),
+            tls: None,
+        })
+    }
 
-        Ok(Response { status, body })
+    // Hands each data frame to the callback as it arrives and returns the trailers
+    async fn stream_body(
+        mut body: hyper::body::Incoming,
+        on_chunk: &mut (dyn FnMut(Bytes) + Send),
+    ) -> Result<Vec<(String, String)>> {
+        let mut trailers = Vec::new();
+        while let Some(frame) = body.frame().await {
+            let frame = frame.map_err(|e| anyhow!("Failed to read response body: {}", e))?;
+            match frame.into_data() {
+                Ok(data) => on_chunk(data),
+                Err(frame) => {
+                    if let Some(frame_trailers) = frame.trailers_ref() {
+                        trailers.extend(header_pairs(frame_trailers));
+                    }
+                }
+            }
+        }
+        Ok(trailers)
+    }
+
+    /// Status, headers and trailers, with the body already handed to a callback
+    async fn to_streamed_response(
+        hyper_response: HyperResponse<hyper::body::Incoming>,
+        options: &ClientOptions,
+        on_chunk: &mut (dyn FnMut(Bytes) + Send),
+    ) -> Result<Response> {
+        let status = hyper_response.status();
+        let headers = Self::collect_headers(hyper_response.headers(), options.max_headers)?;
+        let trailers = Self::stream_body(hyper_response.into_body(), on_chunk).await?;
+
+        Ok(Response {
+            status,
+            headers,
+            body_bytes: Vec::new(),
+            trailers,
+            timing: Timing::default(),
+            tls: None,
+        })
+    }
+
+    fn collect_headers(
+        response_headers: &HeaderMap,
+        max_headers: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        for (name, value) in response_headers {
+            if headers.len() == max_headers {
+                return Err(anyhow!(
+                    "Response has more than {} headers (raise the limit with --max-headers)",
+                    max_headers
+                ));
+            }
+            headers.push(header_pair(name, value));
+        }
+        Ok(headers)
+    }
+}
+
+// The version and cipher suite come from the ServerHello kept by HelloCapture
+fn session_info(
+    tls_stream: &TlsStream<HelloCapture<TcpStream>>,
+    server_name: &str,
+) -> Result<TlsInfo> {
+    let alpn = tls_stream
+        .get_ref()
+        .negotiated_alpn()
+        .map_err(|e| anyhow!("Failed to read the negotiated protocol: {}", e))?
+        .map(|protocol| String::from_utf8_lossy(&protocol).into_owned());
+    let hello = server_hello(&tls_stream.get_ref().get_ref().get_ref().received);
+    Ok(TlsInfo {
+        server_name: server_name.to_string(),
+        alpn,
+        protocol: hello.map(|(version, _)| protocol_name(version)),
+        cipher: hello.map(|(_, suite)| cipher_suite_name(suite)),
+    })
+}
+
+// Reads the negotiated version and cipher suite from a TLS record holding a
+// ServerHello. TLS 1.3 servers keep 1.2 in the legacy version field and put
+// the real one in the supported_versions extension.
+fn server_hello(record: &[u8]) -> Option<(u16, u16)> {
+    let u16_at = |at: usize| Some(u16::from_be_bytes([*record.get(at)?, *record.get(at + 1)?]));
+    // Record header (handshake), then handshake header (server_hello)
+    if record.first() != Some(&0x16) || record.get(5) != Some(&0x02) {
+        return None;
+    }
+    let mut version = u16_at(9)?;
+    // Legacy version and random
+    let mut at = 9 + 2 + 32;
+    at += 1 + *record.get(at)? as usize;
+    let suite = u16_at(at)?;
+    // Cipher suite and compression method
+    at += 3;
+
+    if let Some(length) = u16_at(at) {
+        let end = at + 2 + length as usize;
+        at += 2;
+        while at + 4 <= end {
+            let (kind, length) = (u16_at(at)?, u16_at(at + 2)? as usize);
+            if kind == 0x002b && length == 2 {
+                version = u16_at(at + 4)?;
+            }
+            at += 4 + length;
+        }
     }
+    Some((version, suite))
+}
+
+fn protocol_name(version: u16) -> String {
+    match version {
+        0x0301 => "TLSv1.0".to_string(),
+        0x0302 => "TLSv1.1".to_string(),
+        0x0303 => "TLSv1.2".to_string(),
+        0x0304 => "TLSv1.3".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+// IANA names of the suites servers commonly choose; others are shown by number
+fn cipher_suite_name(suite: u16) -> String {
+    let name = match suite {
+        0x1301 => "TLS_AES_128_GCM_SHA256",
+        0x1302 => "TLS_AES_256_GCM_SHA384",
+        0x1303 => "TLS_CHACHA20_POLY1305_SHA256",
+        0xc02b => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xc02c => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xc02f => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xc030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xcca8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xcca9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xc013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xc014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0x009c => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009d => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x002f => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        other => return format!("0x{:04x}", other),
+    };
+    name.to_string()
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| header_pair(name, value))
+        .collect()
+}
+
+fn header_pair(name: &HeaderName, value: &HeaderValue) -> (String, String) {
+    (
+        name.as_str().to_string(),
+        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+    )
 }
 
 // Converts domain Method enum to hyper::Method
@@ -255,34 +1345,226 @@ impl MethodAdapter {
             DomainMethod::Patch => Method::PATCH,
             DomainMethod::Head => Method::HEAD,
             DomainMethod::Options => Method::OPTIONS,
+            // hyper has no constant for QUERY, but any valid token parses as an extension method
+            DomainMethod::Query => {
+                Method::from_bytes(b"QUERY").expect("QUERY is a valid method token")
+            }
         }
     }
 }
 
-// Converts Option<JsonBody> to hyper body
+// Converts Option<Body> to hyper body
 struct BodyAdapter;
 
 impl BodyAdapter {
-    fn to_hyper_body(domain_body: &Option<JsonBody>) -> Full<Bytes> {
+    fn to_hyper_body(domain_body: &Option<Body>, chunk_size: usize) -> Result<RequestBody> {
         match domain_body {
-            Some(json_body) => Full::new(Bytes::from(json_body.0.clone())),
-            None => Full::new(Bytes::new()),
+            Some(Body::File(file)) => Self::file_body(&file.path, chunk_size),
+            Some(body) => Ok(Self::full_body(Bytes::copy_from_slice(
+                body.as_bytes().unwrap_or_default(),
+            ))),
+            None => Ok(Self::full_body(Bytes::new())),
         }
     }
+
+    fn full_body(bytes: Bytes) -> RequestBody {
+        Full::new(bytes)
+            .map_err(|never| match never {})
+            .boxed_unsync()
+    }
+
+    // Streams the file in fixed-size chunks so it is never fully buffered
+    fn file_body(path: &std::path::Path, chunk_size: usize) -> Result<RequestBody> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow!("Failed to open '{}': {}", path.display(), e))?;
+        let file = tokio::fs::File::from_std(file);
+
+        let chunks = stream::unfold(Some(file), move |file| async move {
+            let mut file = file?;
+            let mut buf = vec![0u8; chunk_size];
+            match file.read(&mut buf).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((Ok(Frame::data(Bytes::from(buf))), Some(file)))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        });
+
+        Ok(StreamBody::new(chunks).boxed_unsync())
+    }
+
+    // Compresses each frame as it is produced, flushing so every chunk on the
+    // wire carries the gzip output for the data read so far
+    fn gzip_body(body: RequestBody) -> RequestBody {
+        let encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+        let chunks = stream::unfold(Some((body, encoder)), |state| async move {
+            let (mut body, mut encoder) = state?;
+            loop {
+                match body.frame().await {
+                    Some(Ok(frame)) => {
+                        let Ok(data) = frame.into_data() else {
+                            continue;
+                        };
+                        if let Err(err) = encoder.write_all(&data).and_then(|_| encoder.flush()) {
+                            return Some((Err(err), None));
+                        }
+                        let compressed = std::mem::take(encoder.get_mut());
+                        if !compressed.is_empty() {
+                            let frame = Frame::data(Bytes::from(compressed));
+                            return Some((Ok(frame), Some((body, encoder))));
+                        }
+                    }
+                    Some(Err(err)) => return Some((Err(err), None)),
+                    None => {
+                        let frame = encoder.finish().map(|rest| Frame::data(Bytes::from(rest)));
+                        return Some((frame, None));
+                    }
+                }
+            }
+        });
+
+        StreamBody::new(chunks).boxed_unsync()
+    }
+
+    // Regroups whatever frames the body produces into frames of exactly
+    // `size` bytes; hyper writes each data frame as one chunk
+    fn rechunk(body: RequestBody, size: usize) -> RequestBody {
+        let chunks = stream::unfold(Some((body, Vec::new())), move |state| async move {
+            let (mut body, mut buffer) = state?;
+            loop {
+                if buffer.len() >= size {
+                    let rest = buffer.split_off(size);
+                    let frame = Frame::data(Bytes::from(std::mem::replace(&mut buffer, rest)));
+                    return Some((Ok(frame), Some((body, buffer))));
+                }
+                match body.frame().await {
+                    Some(Ok(frame)) => {
+                        if let Ok(data) = frame.into_data() {
+                            buffer.extend_from_slice(&data);
+                        }
+                    }
+                    Some(Err(err)) => return Some((Err(err), None)),
+                    None if buffer.is_empty() => return None,
+                    None => return Some((Ok(Frame::data(Bytes::from(buffer))), None)),
+                }
+            }
+        });
+
+        StreamBody::new(chunks).boxed_unsync()
+    }
+
+    // Passes the body through, then sends the trailers as the final frame
+    fn with_trailers(body: RequestBody, trailers: HeaderMap) -> RequestBody {
+        let chunks = stream::unfold(Some((body, trailers)), |state| async move {
+            let (mut body, trailers) = state?;
+            match body.frame().await {
+                Some(Ok(frame)) => Some((Ok(frame), Some((body, trailers)))),
+                Some(Err(err)) => Some((Err(err), None)),
+                None => Some((Ok(Frame::trailers(trailers)), None)),
+            }
+        });
+
+        StreamBody::new(chunks).boxed_unsync()
+    }
 }
 
 // Handles header insertion
 struct HeaderAdapter;
 
 impl HeaderAdapter {
-    fn add_json_content_type(
-        builder: hyper::http::request::Builder,
-        body: &Option<JsonBody>,
-    ) -> hyper::http::request::Builder {
-        if body.is_some() {
-            builder.header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
-        } else {
-            builder
+    // Host, the default Content-Type and Content-Length, then the user's headers in order
+    fn resolve(request: &Request, uri: &Uri, host_header: bool) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+
+        // hyper's low-level client never adds Host itself, so leaving it out
+        // here really sends the request without one
+        if let Some(authority) = uri.authority()
+            && host_header
+        {
+            headers.push((HOST.to_string(), authority.to_string()));
+        }
+
+        let user_content_type = request
+            .headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+        if let Some(body) = &request.body
+            && !user_content_type
+        {
+            headers.push((
+                CONTENT_TYPE.to_string(),
+                body.default_content_type().to_string(),
+            ));
+        }
+
+        let user_content_length = request
+            .headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(CONTENT_LENGTH.as_str()));
+        if let Some(len) = request.body.as_ref().and_then(Body::content_length)
+            && !user_content_length
+        {
+            headers.push((CONTENT_LENGTH.to_string(), len.to_string()));
+        }
+
+        headers.extend(request.headers.iter().cloned());
+        headers
+    }
+
+    // Headers named in the order list move to the front in that order; the
+    // stable sort keeps every other header where it was
+    fn reorder(headers: &mut [(String, String)], order: &[String]) {
+        if order.is_empty() {
+            return;
+        }
+        headers.sort_by_key(|(key, _)| {
+            order
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(key))
+                .unwrap_or(order.len())
+        });
+    }
+
+    // The compressed length is unknown up front, so Content-Length gives way
+    // to chunked framing with gzip applied underneath it
+    fn gzip_transfer_coding(headers: &mut Vec<(String, String)>) {
+        headers.retain(|(key, _)| {
+            !key.eq_ignore_ascii_case(CONTENT_LENGTH.as_str())
+                && !key.eq_ignore_ascii_case(TRANSFER_ENCODING.as_str())
+        });
+        headers.push((TRANSFER_ENCODING.to_string(), "gzip, chunked".to_string()));
+    }
+
+    // Keeps a Transfer-Encoding that is already set (e.g. by --transfer-gzip)
+    fn chunked_transfer_coding(headers: &mut Vec<(String, String)>) {
+        headers.retain(|(key, _)| !key.eq_ignore_ascii_case(CONTENT_LENGTH.as_str()));
+        let chunked = headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(TRANSFER_ENCODING.as_str()));
+        if !chunked {
+            headers.push((TRANSFER_ENCODING.to_string(), "chunked".to_string()));
+        }
+    }
+
+    // hyper drops any trailer not listed in the Trailer header, comparing
+    // against the lowercase name, so the names are announced in lowercase.
+    // A Trailer header the user set is left alone.
+    fn announce_trailers(headers: &mut Vec<(String, String)>, trailers: &[(String, String)]) {
+        let announced = headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(TRAILER.as_str()));
+        if !announced {
+            let mut names: Vec<String> = Vec::new();
+            for (name, _) in trailers {
+                let name = name.to_ascii_lowercase();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            headers.push((TRAILER.to_string(), names.join(", ")));
         }
     }
 
@@ -296,3 +1578,922 @@ impl HeaderAdapter {
         builder
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::services::HttpClient;
+    use crate::domain::entities::RequestBuilder;
+    use crate::domain::value_objects::{FileBody, JsonBody, Url};
+    use hyper::header::HeaderName;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Accepts one connection, answers with `response` and returns the raw request head
+    async fn serve_once(response: &'static [u8]) -> (u16, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                let mut byte = [0u8; 1];
+                stream.read_exact(&mut byte).await.unwrap();
+                head.push(byte[0]);
+            }
+            stream.write_all(response).await.unwrap();
+            String::from_utf8(head).unwrap()
+        });
+        (port, server)
+    }
+
+    async fn capture_request_head() -> (u16, tokio::task::JoinHandle<String>) {
+        serve_once(b"HTTP/1.1 204 No Content\r\n\r\n").await
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn send_logs_a_connect_span_with_the_host_and_port() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::NEW)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (port, server) = capture_request_head().await;
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new(&format!("http://127.0.0.1:{}/", port)).unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+        HyperHttpClient::new(ClientOptions::default())
+            .send(request)
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            logs.contains(&format!("connect{{uri=http://127.0.0.1:{}/}}", port)),
+            "{logs}"
+        );
+        assert!(
+            logs.contains(&format!(
+                "TCP connection established addr=127.0.0.1:{}",
+                port
+            )),
+            "{logs}"
+        );
+    }
+
+    #[test]
+    fn responses_with_too_many_headers_are_rejected() {
+        let mut headers = HeaderMap::new();
+        for i in 0..4 {
+            headers.append(
+                HeaderName::from_str(&format!("x-{}", i)).unwrap(),
+                HeaderValue::from_static("1"),
+            );
+        }
+
+        assert_eq!(
+            ResponseAdapter::collect_headers(&headers, 4).unwrap().len(),
+            4
+        );
+        let error = ResponseAdapter::collect_headers(&headers, 3).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Response has more than 3 headers (raise the limit with --max-headers)"
+        );
+    }
+
+    fn target(url: &str, target: RequestTarget) -> Result<String> {
+        let uri = url.parse::<Uri>().unwrap();
+        RequestAdapter::request_target(&uri, target).map(|uri| uri.to_string())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connect_is_retried_until_it_succeeds() {
+        let attempts = AtomicUsize::new(0);
+        let started = tokio::time::Instant::now();
+        let connected = retry_connect(3, || async {
+            match attempts.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => Err(anyhow!("connection refused")),
+                _ => Ok("connected"),
+            }
+        })
+        .await;
+
+        assert_eq!(connected.unwrap(), "connected");
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        assert_eq!(started.elapsed(), Duration::from_millis(750));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connect_fails_once_the_retries_are_used_up() {
+        let attempts = AtomicUsize::new(0);
+        let connected: Result<()> = retry_connect(2, || async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Err(anyhow!("connection refused"))
+        })
+        .await;
+
+        assert_eq!(connected.unwrap_err().to_string(), "connection refused");
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn request_target_forms() {
+        let url = "http://a.test:8080/p?q=1";
+        assert_eq!(target(url, RequestTarget::Origin).unwrap(), "/p?q=1");
+        assert_eq!(target("http://a.test", RequestTarget::Origin).unwrap(), "/");
+        assert_eq!(target(url, RequestTarget::Absolute).unwrap(), url);
+        assert_eq!(
+            target(url, RequestTarget::Authority).unwrap(),
+            "a.test:8080"
+        );
+        assert_eq!(target(url, RequestTarget::Asterisk).unwrap(), "*");
+        assert!("Proxy".parse::<RequestTarget>().is_err());
+        assert_eq!(
+            "ABSOLUTE".parse::<RequestTarget>().unwrap(),
+            RequestTarget::Absolute
+        );
+    }
+
+    #[test]
+    fn header_order_moves_the_named_headers_first() {
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new("http://a.test/").unwrap(),
+            headers: pairs(&[("Accept", "*/*"), ("X-B", "1"), ("X-A", "2")]),
+            body: None,
+        };
+        let order = ["x-a".to_string(), "HOST".to_string()];
+        assert_eq!(
+            resolved_headers(&request, &order, true),
+            pairs(&[
+                ("X-A", "2"),
+                ("host", "a.test"),
+                ("Accept", "*/*"),
+                ("X-B", "1")
+            ])
+        );
+    }
+
+    #[test]
+    fn host_header_can_be_left_out() {
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new("http://a.test:8080/").unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+        assert_eq!(
+            resolved_headers(&request, &[], true),
+            pairs(&[("host", "a.test:8080")])
+        );
+        assert!(resolved_headers(&request, &[], false).is_empty());
+    }
+
+    #[tokio::test]
+    async fn trailers_follow_a_chunked_body() {
+        let options = ClientOptions {
+            trailers: pairs(&[("X-Sum", "1"), ("Server-Timing", "a"), ("x-sum", "2")]),
+            ..ClientOptions::default()
+        };
+        let request = post("{}");
+        let uri = request.url.0.clone();
+        let hyper_request = RequestAdapter::to_hyper_request(request, &uri, &options).unwrap();
+        assert_eq!(
+            header(&hyper_request, "trailer"),
+            Some("x-sum, server-timing")
+        );
+        assert_eq!(header(&hyper_request, "transfer-encoding"), Some("chunked"));
+        assert_eq!(header(&hyper_request, "content-length"), None);
+
+        let body = hyper_request.into_body().collect().await.unwrap();
+        let trailers = body.trailers().cloned().unwrap();
+        assert_eq!(trailers.get_all("x-sum").iter().count(), 2);
+        assert_eq!(body.to_bytes(), "{}");
+    }
+
+    #[test]
+    fn trailers_are_left_off_requests_without_a_body() {
+        let options = ClientOptions {
+            trailers: pairs(&[("X-Sum", "1")]),
+            ..ClientOptions::default()
+        };
+        let request = RequestBuilder::new(DomainMethod::Get)
+            .base_url("http://a.test/")
+            .build()
+            .unwrap();
+        let uri = request.url.0.clone();
+        let hyper_request = RequestAdapter::to_hyper_request(request, &uri, &options).unwrap();
+        assert_eq!(header(&hyper_request, "trailer"), None);
+        assert_eq!(header(&hyper_request, "transfer-encoding"), None);
+    }
+
+    #[test]
+    fn invalid_trailers_are_errors() {
+        let error = RequestAdapter::trailer_map(&pairs(&[("bad name", "x")])).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid trailer name 'bad name'")
+        );
+        let error = RequestAdapter::trailer_map(&pairs(&[("X-Ok", "line\nbreak")])).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid value for trailer 'x-ok'")
+        );
+    }
+
+    #[test]
+    fn request_targets_keep_encoded_slashes_and_dot_segments() {
+        let url = "http://a.test/a%2Fb/../c/./d?x=%2F";
+        assert_eq!(
+            target(url, RequestTarget::Origin).unwrap(),
+            "/a%2Fb/../c/./d?x=%2F"
+        );
+        assert_eq!(target(url, RequestTarget::Absolute).unwrap(), url);
+    }
+
+    const CHUNKED_WITH_TRAILER: &[u8] = b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\
+        trailer: x-checksum\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\nx-checksum: abc\r\n\r\n";
+
+    async fn get_from(port: u16, options: ClientOptions) -> Response {
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new(&format!("http://127.0.0.1:{}/", port)).unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+        HyperHttpClient::new(options).send(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn discarded_bodies_are_drained_but_not_stored() {
+        let (port, server) = serve_once(CHUNKED_WITH_TRAILER).await;
+        let options = ClientOptions {
+            discard_body: true,
+            ..ClientOptions::default()
+        };
+        let response = get_from(port, options).await;
+        server.await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert!(response.body_bytes.is_empty());
+        assert!(
+            response
+                .headers
+                .contains(&("trailer".to_string(), "x-checksum".to_string()))
+        );
+        assert_eq!(
+            response.trailers,
+            [("x-checksum".to_string(), "abc".to_string())]
+        );
+
+        let (port, server) = serve_once(CHUNKED_WITH_TRAILER).await;
+        let response = get_from(port, ClientOptions::default()).await;
+        server.await.unwrap();
+        assert_eq!(response.body_bytes, b"hello world");
+        assert_eq!(
+            response.trailers,
+            [("x-checksum".to_string(), "abc".to_string())]
+        );
+    }
+
+    // A connected TCP stream to a local listener
+    async fn local_stream() -> TcpStream {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { listener.accept().await });
+        TcpStream::connect(addr).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn keepalive_is_enabled_only_with_a_time() {
+        let stream = local_stream().await;
+        SocketConfig::from_options(&ClientOptions::default())
+            .apply(&stream)
+            .unwrap();
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+
+        let stream = local_stream().await;
+        let options = ClientOptions {
+            keepalive_time: Some(Duration::from_secs(45)),
+            ..ClientOptions::default()
+        };
+        SocketConfig::from_options(&options).apply(&stream).unwrap();
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(45));
+    }
+
+    #[cfg(not(feature = "http3"))]
+    #[tokio::test]
+    async fn http3_needs_the_feature() {
+        let client = HyperHttpClient::new(ClientOptions {
+            http3: true,
+            ..ClientOptions::default()
+        });
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new("https://a.test/").unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+        assert_eq!(
+            client.send(request).await.unwrap_err().to_string(),
+            "HTTP/3 support is not enabled; rebuild Hurl with --features http3"
+        );
+    }
+
+    fn pairs(headers: &[(&str, &str)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    // Accepts at most three bytes per write, so names are split across writes
+    struct Trickle(Vec<u8>);
+
+    impl AsyncWrite for Trickle {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn typed_header_names_survive_writes_that_split_them() {
+        let casing = HeaderCasing::new(&pairs(&[("X-API-key", "1"), ("x-a", "2"), ("X-A", "3")]));
+        let mut io = TokioIoAdapter::new(Trickle(Vec::new()), None, Some(casing));
+        let head =
+            b"GET / HTTP/1.1\r\nhost: a.test\r\nx-api-key: 1\r\nx-a: 2\r\nx-a: 3\r\n\r\nx-a: body";
+
+        let mut rest = &head[..];
+        while !rest.is_empty() {
+            let n = std::future::poll_fn(|cx| {
+                hyper::rt::Write::poll_write(Pin::new(&mut io), cx, rest)
+            })
+            .await
+            .unwrap();
+            rest = &rest[n..];
+        }
+
+        assert_eq!(
+            String::from_utf8(io.inner.0).unwrap(),
+            "GET / HTTP/1.1\r\nhost: a.test\r\nX-API-key: 1\r\nx-a: 2\r\nX-A: 3\r\n\r\nx-a: body"
+        );
+    }
+
+    async fn sent_head(options: ClientOptions) -> String {
+        let (port, server) = capture_request_head().await;
+        let request = Request {
+            method: DomainMethod::Get,
+            url: Url::new(&format!("http://127.0.0.1:{}/", port)).unwrap(),
+            headers: pairs(&[("X-API-key", "k"), ("x-Trace-ID", "1")]),
+            body: None,
+        };
+        HyperHttpClient::new(options).send(request).await.unwrap();
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn header_names_go_on_the_wire_as_typed_when_asked() {
+        let head = sent_head(ClientOptions {
+            header_case_sensitive: true,
+            ..ClientOptions::default()
+        })
+        .await;
+        assert!(head.contains("\r\nhost: 127.0.0.1:"), "{head}");
+        assert!(head.contains("\r\nX-API-key: k\r\n"), "{head}");
+        assert!(head.contains("\r\nx-Trace-ID: 1\r\n"), "{head}");
+
+        let head = sent_head(ClientOptions::default()).await;
+        assert!(head.contains("\r\nx-api-key: k\r\n"), "{head}");
+
+        let head = sent_head(ClientOptions {
+            title_case_headers: true,
+            ..ClientOptions::default()
+        })
+        .await;
+        assert!(head.contains("\r\nX-Api-Key: k\r\n"), "{head}");
+    }
+
+    // Accepts one connection and returns the server name from its TLS
+    // ClientHello, or None when the client sent no SNI extension
+    async fn client_hello_sni() -> (u16, tokio::task::JoinHandle<Option<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut record_header = [0u8; 5];
+            stream.read_exact(&mut record_header).await.unwrap();
+            let length = u16::from_be_bytes([record_header[3], record_header[4]]) as usize;
+            let mut hello = vec![0u8; length];
+            stream.read_exact(&mut hello).await.unwrap();
+
+            let u16_at = |at: usize| u16::from_be_bytes([hello[at], hello[at + 1]]) as usize;
+            // Handshake header, client version and random
+            let mut at = 4 + 2 + 32;
+            at += 1 + hello[at] as usize;
+            at += 2 + u16_at(at);
+            at += 1 + hello[at] as usize;
+            let extensions_end = at + 2 + u16_at(at);
+            at += 2;
+            while at < extensions_end {
+                let (kind, length) = (u16_at(at), u16_at(at + 2));
+                if kind == 0 {
+                    let name_length = u16_at(at + 7);
+                    let name = &hello[at + 9..at + 9 + name_length];
+                    return Some(String::from_utf8(name.to_vec()).unwrap());
+                }
+                at += 4 + length;
+            }
+            None
+        });
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn the_tls_server_name_is_the_sni_override_or_the_host() {
+        for (sni, expected) in [(Some("api.test"), "api.test"), (None, "localhost")] {
+            let (port, server) = client_hello_sni().await;
+            let request = Request {
+                method: DomainMethod::Get,
+                url: Url::new(&format!("https://localhost:{}/", port)).unwrap(),
+                headers: Vec::new(),
+                body: None,
+            };
+            let client = HyperHttpClient::new(ClientOptions {
+                sni: sni.map(str::to_string),
+                ..ClientOptions::default()
+            });
+
+            // The listener hangs up after reading the hello, failing the handshake
+            assert!(client.send(request).await.is_err());
+            assert_eq!(server.await.unwrap().as_deref(), Some(expected));
+        }
+    }
+
+    // Accepts one request and returns its head and body, decoding a chunked body
+    async fn receive_upload() -> (u16, tokio::task::JoinHandle<(String, Vec<u8>)>) {
+        use tokio::io::AsyncBufReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = tokio::io::BufReader::new(stream);
+            let mut head = String::new();
+            while !head.ends_with("\r\n\r\n") {
+                stream.read_line(&mut head).await.unwrap();
+            }
+            let lower = head.to_lowercase();
+
+            let mut body = Vec::new();
+            if lower.contains("transfer-encoding: chunked") {
+                loop {
+                    let mut size = String::new();
+                    stream.read_line(&mut size).await.unwrap();
+                    let size = usize::from_str_radix(size.trim(), 16).unwrap();
+                    let mut chunk = vec![0u8; size + 2];
+                    stream.read_exact(&mut chunk).await.unwrap();
+                    if size == 0 {
+                        break;
+                    }
+                    body.extend_from_slice(&chunk[..size]);
+                }
+            } else if let Some(length) = lower
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+            {
+                body.resize(length.parse().unwrap(), 0);
+                stream.read_exact(&mut body).await.unwrap();
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            (head, body)
+        });
+        (port, server)
+    }
+
+    async fn upload_to(port: u16, body: FileBody) {
+        let request = Request {
+            method: DomainMethod::Put,
+            url: Url::new(&format!("http://127.0.0.1:{}/upload", port)).unwrap(),
+            headers: Vec::new(),
+            body: Some(Body::File(body)),
+        };
+        let response = HyperHttpClient::new(ClientOptions::default())
+            .send(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 204);
+    }
+
+    #[tokio::test]
+    async fn uploaded_files_arrive_whole_with_their_length() {
+        let contents: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("hurl-upload-test-{}", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+
+        let (port, server) = receive_upload().await;
+        upload_to(port, FileBody::new(path.to_str().unwrap()).unwrap()).await;
+        let (head, body) = server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(head.starts_with("PUT /upload HTTP/1.1\r\n"), "{head}");
+        assert!(head.contains("\r\ncontent-length: 200000\r\n"), "{head}");
+        assert!(body == contents, "received {} bytes", body.len());
+    }
+
+    #[tokio::test]
+    async fn small_buffer_sizes_still_stream_the_whole_file() {
+        let contents: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("hurl-buffer-test-{}", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+
+        for buffer_size in [0, 1, MIN_BUFFER_SIZE + 1] {
+            let options = ClientOptions {
+                buffer_size: Some(buffer_size),
+                ..ClientOptions::default()
+            };
+            let request = Request {
+                method: DomainMethod::Post,
+                url: Url::new("http://a.test/").unwrap(),
+                headers: Vec::new(),
+                body: Some(Body::File(FileBody::new(path.to_str().unwrap()).unwrap())),
+            };
+            let uri = request.url.0.clone();
+            let mut body = RequestAdapter::to_hyper_request(request, &uri, &options)
+                .unwrap()
+                .into_body();
+
+            let mut chunks = Vec::new();
+            while let Some(frame) = body.frame().await {
+                chunks.push(frame.unwrap().into_data().unwrap());
+            }
+            assert_eq!(chunks[0].len(), buffer_size.max(MIN_BUFFER_SIZE));
+            assert_eq!(chunks.concat(), contents, "buffer size {buffer_size}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn post(body: &str) -> Request {
+        Request {
+            method: DomainMethod::Post,
+            url: Url::new("http://a.test/").unwrap(),
+            headers: Vec::new(),
+            body: Some(Body::Json(JsonBody::new(body).unwrap())),
+        }
+    }
+
+    fn header<'a>(request: &'a HyperRequest<RequestBody>, name: &str) -> Option<&'a str> {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn gzip_bodies_are_compressed_and_sent_chunked() {
+        let options = ClientOptions {
+            transfer_gzip: true,
+            ..ClientOptions::default()
+        };
+        let request = post(r#"{"text":"hello hello hello"}"#);
+        let uri = request.url.0.clone();
+        let hyper_request = RequestAdapter::to_hyper_request(request, &uri, &options).unwrap();
+        assert_eq!(
+            header(&hyper_request, "transfer-encoding"),
+            Some("gzip, chunked")
+        );
+        assert_eq!(header(&hyper_request, "content-length"), None);
+
+        let compressed = hyper_request
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        let mut decoded = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(&compressed[..]),
+            &mut decoded,
+        )
+        .unwrap();
+        assert_eq!(decoded, r#"{"text":"hello hello hello"}"#);
+    }
+
+    #[test]
+    fn tls_verification_failures_get_a_hint() {
+        let verify_failed = "error:0A000086:SSL routines:tls_post_process_server_certificate:\
+                             certificate verify failed:../ssl/statem/statem_clnt.c:1889:";
+        let cases = [
+            (
+                format!("{} (certificate has expired)", verify_failed),
+                "the server's certificate is expired or not yet valid",
+            ),
+            (
+                format!("{} (hostname mismatch)", verify_failed),
+                "the server's certificate is not valid for 'api.test' (use --sni",
+            ),
+            (
+                format!("{} (unable to get local issuer certificate)", verify_failed),
+                "the server's certificate is not signed by a trusted root",
+            ),
+            (
+                format!(
+                    "{} (self-signed certificate in certificate chain)",
+                    verify_failed
+                ),
+                "the server's certificate is not signed by a trusted root",
+            ),
+            (
+                "error:0A00010B:SSL routines:ssl3_get_record:wrong version number".to_string(),
+                "the server did not answer with TLS",
+            ),
+        ];
+        for (message, hint) in cases {
+            let error = tls_handshake_error(&message, "api.test").to_string();
+            assert!(
+                error.starts_with(&format!("TLS handshake failed: {}", hint)),
+                "{error}"
+            );
+            assert!(error.ends_with(&message), "{error}");
+        }
+
+        let other = tls_handshake_error(&"connection reset", "api.test");
+        assert_eq!(other.to_string(), "TLS handshake failed: connection reset");
+    }
+
+    #[tokio::test]
+    async fn connect_only_reaches_the_server_without_sending_a_request() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).await.unwrap();
+            received
+        });
+
+        let url = Url::new(&format!("http://{}/", addr)).unwrap();
+        let info = HyperHttpClient::new(ClientOptions::default())
+            .connect(&url)
+            .await
+            .unwrap();
+
+        assert_eq!(info.peer, addr.to_string());
+        assert!(info.tls.is_none());
+        assert!(server.await.unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replayed_chunks_keep_their_recorded_gap_scaled_by_the_speed() {
+        use crate::infrastructure::transcript::TranscriptEvent;
+        use base64::Engine;
+
+        let event = |direction, elapsed_micros, data: &[u8]| TranscriptEvent {
+            connection: 0,
+            direction,
+            elapsed_micros,
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        };
+        let transcript = Arc::new(WireTranscript {
+            events: vec![
+                event(Direction::Sent, 0, b"GET / HTTP/1.1\r\n\r\n"),
+                event(
+                    Direction::Received,
+                    100_000,
+                    b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n",
+                ),
+                event(Direction::Received, 500_000, b"0\r\n\r\n"),
+            ],
+        });
+
+        for (speed, gap) in [(1.0, 400), (2.0, 200), (0.5, 800)] {
+            let client = HyperHttpClient::new(ClientOptions {
+                replay: Some(transcript.clone()),
+                replay_speed: Some(speed),
+                ..ClientOptions::default()
+            });
+            let request = Request {
+                method: DomainMethod::Get,
+                url: Url::new("http://a.test/").unwrap(),
+                headers: Vec::new(),
+                body: None,
+            };
+
+            let mut first_chunk = None;
+            client
+                .send_streaming(request, &mut |_| {
+                    first_chunk.get_or_insert_with(tokio::time::Instant::now);
+                })
+                .await
+                .unwrap();
+
+            assert_eq!(
+                first_chunk.unwrap().elapsed(),
+                Duration::from_millis(gap),
+                "speed {speed}"
+            );
+        }
+    }
+
+    // A ServerHello record with the given legacy version, cipher suite and extensions
+    fn server_hello_record(version: u16, suite: u16, extensions: &[u8]) -> Vec<u8> {
+        let mut body = version.to_be_bytes().to_vec();
+        body.extend_from_slice(&[7; 32]);
+        body.extend_from_slice(&[4, 1, 2, 3, 4]);
+        body.extend_from_slice(&suite.to_be_bytes());
+        body.push(0);
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(extensions);
+
+        let mut handshake = vec![0x02, 0, 0, body.len() as u8];
+        handshake.extend_from_slice(&body);
+        let mut record = vec![0x16, 0x03, 0x03];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[test]
+    fn server_hellos_give_the_version_and_cipher_suite() {
+        let tls12 = server_hello_record(0x0303, 0xc02f, &[0xff, 0x01, 0, 1, 0]);
+        assert_eq!(server_hello(&tls12), Some((0x0303, 0xc02f)));
+
+        // supported_versions overrides the legacy version field
+        let tls13 = server_hello_record(0x0303, 0x1302, &[0, 0x33, 0, 0, 0, 0x2b, 0, 2, 3, 4]);
+        assert_eq!(server_hello(&tls13), Some((0x0304, 0x1302)));
+        assert_eq!(protocol_name(0x0304), "TLSv1.3");
+        assert_eq!(cipher_suite_name(0x1302), "TLS_AES_256_GCM_SHA384");
+        assert_eq!(cipher_suite_name(0x00ff), "0x00ff");
+
+        assert_eq!(server_hello(&tls13[..20]), None);
+        assert_eq!(server_hello(b"HTTP/1.1 200 OK\r\n"), None);
+    }
+
+    #[tokio::test]
+    async fn hello_capture_keeps_only_the_first_record() {
+        let mut bytes = server_hello_record(0x0303, 0xc02f, &[]);
+        let hello_length = bytes.len();
+        bytes.extend_from_slice(b"\x16\x03\x03\x00\x02ab");
+
+        let mut capture = HelloCapture::new(bytes.as_slice());
+        let mut read = Vec::new();
+        capture.read_to_end(&mut read).await.unwrap();
+
+        assert_eq!(read, bytes);
+        assert_eq!(capture.received, bytes[..hello_length]);
+    }
+
+    #[tokio::test]
+    async fn nodelay_is_on_by_default_and_off_with_the_flag() {
+        let stream = local_stream().await;
+        SocketConfig::from_options(&ClientOptions::default())
+            .apply(&stream)
+            .unwrap();
+        assert!(stream.nodelay().unwrap());
+
+        let stream = local_stream().await;
+        let options = ClientOptions {
+            tcp_nodelay: false,
+            ..ClientOptions::default()
+        };
+        SocketConfig::from_options(&options).apply(&stream).unwrap();
+        assert!(!stream.nodelay().unwrap());
+    }
+
+    #[test]
+    fn query_maps_to_an_extension_method() {
+        let method = MethodAdapter::to_hyper_method(DomainMethod::Query);
+        assert_eq!(method.as_str(), "QUERY");
+    }
+
+    #[tokio::test]
+    async fn query_requests_carry_their_body() {
+        let request = Request {
+            method: DomainMethod::Query,
+            url: Url::new("http://a.test/search").unwrap(),
+            headers: Vec::new(),
+            body: Some(Body::Json(JsonBody::new(r#"{"q":"rust"}"#).unwrap())),
+        };
+        let uri = request.url.0.clone();
+        let hyper_request =
+            RequestAdapter::to_hyper_request(request, &uri, &ClientOptions::default()).unwrap();
+
+        assert_eq!(hyper_request.method().as_str(), "QUERY");
+        assert_eq!(
+            header(&hyper_request, "content-type"),
+            Some("application/json")
+        );
+        assert_eq!(header(&hyper_request, "content-length"), Some("12"));
+        let body = hyper_request
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(body, r#"{"q":"rust"}"#);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn fifo_bodies_are_streamed_chunked_until_the_writer_closes() {
+        let path = std::env::temp_dir().join(format!("hurl-fifo-test-{}", std::process::id()));
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        // Opening for writing blocks until the upload opens the pipe for reading
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            let mut pipe = std::fs::OpenOptions::new()
+                .write(true)
+                .open(writer_path)
+                .unwrap();
+            for part in [&b"first,"[..], b"second,", b"third"] {
+                pipe.write_all(part).unwrap();
+                pipe.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let (port, server) = receive_upload().await;
+        upload_to(port, FileBody::fifo(path.to_str().unwrap()).unwrap()).await;
+        let (head, body) = server.await.unwrap();
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            head.contains("\r\ntransfer-encoding: chunked\r\n"),
+            "{head}"
+        );
+        assert!(!head.contains("content-length"), "{head}");
+        assert_eq!(body, b"first,second,third");
+    }
+
+    #[tokio::test]
+    async fn bodies_are_sent_in_chunks_of_the_given_size() {
+        let options = ClientOptions {
+            chunk_size: Some(4),
+            ..ClientOptions::default()
+        };
+        let request = post(r#"{"a":12}"#);
+        let uri = request.url.0.clone();
+        let hyper_request = RequestAdapter::to_hyper_request(request, &uri, &options).unwrap();
+        assert_eq!(header(&hyper_request, "transfer-encoding"), Some("chunked"));
+        assert_eq!(header(&hyper_request, "content-length"), None);
+
+        let mut body = hyper_request.into_body();
+        let mut chunks = Vec::new();
+        while let Some(frame) = body.frame().await {
+            chunks.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(chunks, ["{\"a\"", ":12}"]);
+    }
+}
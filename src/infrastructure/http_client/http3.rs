@@ -0,0 +1,210 @@
+//! Experimental HTTP/3 transport over QUIC, used for `--http3`
+//!
+//! Requests are built by the same adapters as the HTTP/1 path and then sent
+//! as HTTP/3 frames, so headers, bodies and trailers behave the same way.
+
+use super::{ClientOptions, RequestAdapter, ResponseAdapter, header_pairs, target_addr};
+use crate::domain::entities::{Request, Response, Timing, TlsInfo};
+use anyhow::{Result, anyhow};
+use http_body_util::BodyExt;
+use hyper::body::{Buf, Bytes};
+use hyper::header::{CONNECTION, HOST, TRANSFER_ENCODING};
+use quinn::crypto::rustls::QuicClientConfig;
+use rustls_platform_verifier::BuilderVerifierExt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// How long to wait for the QUIC handshake before deciding the server does not speak HTTP/3
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ALPN protocol identifier for HTTP/3
+const ALPN_H3: &[u8] = b"h3";
+
+/// Sends `request` over a new HTTP/3 connection, handing the body to `on_chunk`
+/// as it arrives when given one and collecting it into the response otherwise
+#[instrument(name = "http3", skip_all, fields(url = %request.url.as_str()))]
+pub(super) async fn send(
+    request: Request,
+    options: &ClientOptions,
+    mut on_chunk: Option<&mut (dyn FnMut(Bytes) + Send)>,
+) -> Result<Response> {
+    let uri = request.url.0.clone();
+    if uri.scheme_str() != Some("https") {
+        return Err(anyhow!(
+            "--http3 needs an https:// URL; HTTP/3 always runs over TLS"
+        ));
+    }
+    let started = Instant::now();
+    let (host, addr) = target_addr(&uri)?;
+    let server_name = options.sni.as_deref().unwrap_or(host).to_string();
+    let (endpoint, connection) = connect(&addr, &server_name).await?;
+    let connect = started.elapsed();
+
+    let (mut driver, mut sender) = h3::client::new(h3_quinn::Connection::new(connection))
+        .await
+        .map_err(|e| anyhow!("HTTP/3 handshake failed: {}", e))?;
+    let driver = tokio::spawn(async move { driver.wait_idle().await });
+
+    let hyper_request = RequestAdapter::to_hyper_request(request, &uri, options)?;
+    let (mut parts, mut body) = hyper_request.into_parts();
+    // The authority travels in the :authority pseudo-header, and HTTP/3 has
+    // no connection-level headers or transfer codings
+    parts.uri = uri;
+    for name in [HOST, CONNECTION, TRANSFER_ENCODING] {
+        parts.headers.remove(name);
+    }
+
+    let mut stream = sender
+        .send_request(hyper::Request::from_parts(parts, ()))
+        .await
+        .map_err(|e| anyhow!("Failed to send HTTP/3 request: {}", e))?;
+    let mut sent_trailers = false;
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|e| anyhow!("Failed to read request body: {}", e))?;
+        match frame.into_data() {
+            Ok(data) => stream.send_data(data).await,
+            Err(frame) => match frame.into_trailers() {
+                Ok(trailers) => {
+                    sent_trailers = true;
+                    stream.send_trailers(trailers).await
+                }
+                Err(_) => Ok(()),
+            },
+        }
+        .map_err(|e| anyhow!("Failed to send HTTP/3 request body: {}", e))?;
+    }
+    // Sending trailers already closed our side of the stream
+    if !sent_trailers {
+        stream
+            .finish()
+            .await
+            .map_err(|e| anyhow!("Failed to send HTTP/3 request: {}", e))?;
+    }
+
+    let head = stream
+        .recv_response()
+        .await
+        .map_err(|e| anyhow!("Failed to read HTTP/3 response: {}", e))?;
+    debug!(status = %head.status(), "Received response");
+    let headers = ResponseAdapter::collect_headers(head.headers(), options.max_headers)?;
+
+    let mut body_bytes = Vec::new();
+    while let Some(mut chunk) = stream
+        .recv_data()
+        .await
+        .map_err(|e| anyhow!("Failed to read response body: {}", e))?
+    {
+        let data = chunk.copy_to_bytes(chunk.remaining());
+        match on_chunk.as_mut() {
+            Some(on_chunk) => on_chunk(data),
+            None if options.discard_body => {}
+            None => body_bytes.extend_from_slice(&data),
+        }
+    }
+    let trailers = stream
+        .recv_trailers()
+        .await
+        .map_err(|e| anyhow!("Failed to read response trailers: {}", e))?
+        .map(|trailers| header_pairs(&trailers))
+        .unwrap_or_default();
+
+    drop(stream);
+    drop(sender);
+    driver.abort();
+    endpoint.close(0u32.into(), b"done");
+
+    Ok(Response {
+        status: head.status(),
+        headers,
+        body_bytes,
+        trailers,
+        timing: Timing {
+            connect,
+            total: started.elapsed(),
+        },
+        tls: Some(TlsInfo {
+            server_name,
+            alpn: Some(String::from_utf8_lossy(ALPN_H3).into_owned()),
+            // QUIC always runs TLS 1.3; quinn does not report the cipher suite
+            protocol: Some("TLSv1.3".to_string()),
+            cipher: None,
+        }),
+    })
+}
+
+// Opens a QUIC connection offering only h3 over ALPN, so a handshake that
+// succeeds is one the server agreed to speak HTTP/3 on
+async fn connect(addr: &str, server_name: &str) -> Result<(quinn::Endpoint, quinn::Connection)> {
+    let remote = tokio::net::lookup_host(addr)
+        .await
+        .map_err(|e| anyhow!("Failed to resolve {}: {}", addr, e))?
+        .next()
+        .ok_or_else(|| anyhow!("Failed to resolve {}: no addresses found", addr))?;
+
+    let local: SocketAddr = if remote.is_ipv6() {
+        "[::]:0".parse()?
+    } else {
+        "0.0.0.0:0".parse()?
+    };
+    let mut endpoint = quinn::Endpoint::client(local)
+        .map_err(|e| anyhow!("Failed to open a UDP socket: {}", e))?;
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(tls_config()?)));
+
+    let connecting = endpoint
+        .connect(remote, server_name)
+        .map_err(|e| anyhow!("Failed to connect to {}: {}", addr, e))?;
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, connecting).await {
+        Ok(Ok(connection)) => {
+            debug!(%remote, server_name, "QUIC connection established");
+            Ok((endpoint, connection))
+        }
+        Ok(Err(err)) => Err(handshake_error(&err, addr)),
+        Err(_) => Err(anyhow!(
+            "No HTTP/3 answer from {} within {}s; the server may not support HTTP/3 \
+             (try again without --http3)",
+            addr,
+            HANDSHAKE_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+// TLS 1.3 with the platform's trust store, which QUIC requires
+fn tls_config() -> Result<QuicClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .and_then(|builder| builder.with_platform_verifier())
+        .map_err(|e| anyhow!("Failed to create TLS configuration: {}", e))?
+        .with_no_client_auth();
+    config.alpn_protocols = vec![ALPN_H3.to_vec()];
+    QuicClientConfig::try_from(config)
+        .map_err(|e| anyhow!("Failed to create QUIC configuration: {}", e))
+}
+
+// A server that answers QUIC but not HTTP/3 rejects the h3 ALPN offer with
+// TLS alert 120 (no_application_protocol)
+fn handshake_error(err: &quinn::ConnectionError, addr: &str) -> anyhow::Error {
+    let no_h3 = quinn::TransportErrorCode::crypto(120);
+    let rejected_h3 = match err {
+        quinn::ConnectionError::ConnectionClosed(close) => close.error_code == no_h3,
+        quinn::ConnectionError::TransportError(error) => error.code == no_h3,
+        _ => false,
+    };
+    if rejected_h3 {
+        anyhow!(
+            "{} does not support HTTP/3 (try again without --http3): {}",
+            addr,
+            err
+        )
+    } else if matches!(err, quinn::ConnectionError::TimedOut) {
+        anyhow!(
+            "No HTTP/3 answer from {}; the server may not support HTTP/3 \
+             (try again without --http3)",
+            addr
+        )
+    } else {
+        anyhow!("HTTP/3 connection to {} failed: {}", addr, err)
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Result, anyhow};
+use std::io::IsTerminal;
+use tracing_subscriber::EnvFilter;
+
+/// Default filter used when neither `--log-level` nor `RUST_LOG` is set
+const DEFAULT_FILTER: &str = "warn";
+
+/// Installs the global tracing subscriber
+///
+/// Structured logs are always written to stderr so they never mix with the
+/// response output on stdout.
+///
+/// # Arguments
+/// * `log_level` - Explicit level or filter directive; takes precedence over `RUST_LOG`
+///
+/// # Returns
+/// * `Ok(())` - Subscriber installed
+/// * `Err(anyhow::Error)` - If the filter directive is invalid
+pub fn init(log_level: Option<&str>) -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(filter(log_level)?)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .try_init()
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))
+}
+
+// An explicit level wins over RUST_LOG, which wins over the default
+fn filter(log_level: Option<&str>) -> Result<EnvFilter> {
+    match log_level {
+        Some(level) => {
+            EnvFilter::try_new(level).map_err(|e| anyhow!("Invalid log level '{}': {}", level, e))
+        }
+        None => Ok(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::level_filters::LevelFilter;
+
+    #[test]
+    fn explicit_level_sets_the_filter() {
+        let filter = filter(Some("debug")).unwrap();
+        assert_eq!(filter.max_level_hint(), Some(LevelFilter::DEBUG));
+    }
+
+    #[test]
+    fn explicit_directives_are_kept_per_target() {
+        let filter = filter(Some("warn,hurl=trace")).unwrap();
+        assert_eq!(filter.to_string(), "hurl=trace,warn");
+    }
+
+    #[test]
+    fn invalid_level_is_an_error() {
+        let error = filter(Some("hurl=loud")).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid log level 'hurl=loud'")
+        );
+    }
+}
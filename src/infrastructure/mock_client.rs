@@ -0,0 +1,291 @@
+use crate::application::services::HttpClient;
+use crate::domain::entities::{ConnectionInfo, Method, Request, Response, Timing};
+use crate::domain::value_objects::Url;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use hyper::StatusCode;
+use hyper::body::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+enum Reply {
+    Response(Response),
+    Error(String),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Replies queued per `METHOD url`
+    routes: HashMap<String, VecDeque<Reply>>,
+    calls: Vec<Request>,
+    /// How long every reply takes to arrive
+    delay: Duration,
+}
+
+/// `HttpClient` that answers from canned responses, for testing code built
+/// on `HttpRequestService` without a server
+///
+/// Replies are registered per method and URL. Several replies for the same
+/// route are returned in order, and the last one repeats once the others are
+/// used up. Clones share their routes and call log, so keep a clone to inspect
+/// calls after boxing one into the service:
+///
+/// ```
+/// # use hurl::application::services::HttpRequestService;
+/// # use hurl::domain::entities::{Method, RequestBuilder};
+/// # use hurl::infrastructure::mock_client::MockHttpClient;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mock = MockHttpClient::new()
+///     .on(Method::Get, "http://api.test/users", MockHttpClient::reply(200, "[]"))
+///     .on_error(Method::Post, "http://api.test/users", "connection reset");
+/// let service = HttpRequestService::new(Box::new(mock.clone()));
+///
+/// let request = RequestBuilder::new(Method::Get).base_url("http://api.test/users").build()?;
+/// let response = service.send_request(request).await?;
+/// assert_eq!(response.text(), "[]");
+/// mock.assert_called(Method::Get, "http://api.test/users", 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockHttpClient {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a response with the given status and body and no headers
+    pub fn reply(status: u16, body: &str) -> Response {
+        Response {
+            status: StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            headers: Vec::new(),
+            body_bytes: body.as_bytes().to_vec(),
+            trailers: Vec::new(),
+            timing: Timing::default(),
+            tls: None,
+        }
+    }
+
+    /// Queues a response for requests with this method and URL
+    pub fn on(self, method: Method, url: &str, response: Response) -> Self {
+        self.push(&method, url, Reply::Response(response));
+        self
+    }
+
+    /// Queues a failure, returned as the error from `send`
+    pub fn on_error(self, method: Method, url: &str, message: &str) -> Self {
+        self.push(&method, url, Reply::Error(message.to_string()));
+        self
+    }
+
+    /// Makes every reply take `delay` to arrive, like a slow server
+    pub fn with_delay(self, delay: Duration) -> Self {
+        self.lock().delay = delay;
+        self
+    }
+
+    /// Every request received so far, in order
+    pub fn calls(&self) -> Vec<Request> {
+        self.lock().calls.clone()
+    }
+
+    /// How many requests were received for this method and URL
+    pub fn call_count(&self, method: Method, url: &str) -> usize {
+        let key = route_key(&method, &normalize(url));
+        self.lock()
+            .calls
+            .iter()
+            .filter(|request| route_key(&request.method, &request.url.as_str()) == key)
+            .count()
+    }
+
+    /// Panics unless exactly `times` requests were received for this method and URL
+    pub fn assert_called(&self, method: Method, url: &str, times: usize) {
+        let label = route_key(&method, &normalize(url));
+        let count = self.call_count(method, url);
+        assert_eq!(
+            count, times,
+            "expected {} call(s) to {}, got {}",
+            times, label, count
+        );
+    }
+
+    fn push(&self, method: &Method, url: &str, reply: Reply) {
+        self.lock()
+            .routes
+            .entry(route_key(method, &normalize(url)))
+            .or_default()
+            .push_back(reply);
+    }
+
+    // Records the call and takes the next reply, keeping the last one for repeats;
+    // the call is logged before any delay, so requests that time out still show up
+    async fn answer(&self, request: Request) -> Result<Response> {
+        let (reply, delay) = self.next_reply(request);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        reply
+    }
+
+    fn next_reply(&self, request: Request) -> (Result<Response>, Duration) {
+        let key = route_key(&request.method, &request.url.as_str());
+        let mut state = self.lock();
+        state.calls.push(request);
+
+        let reply = match state.routes.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        };
+        let reply = match reply {
+            Some(Reply::Response(response)) => Ok(response),
+            Some(Reply::Error(message)) => Err(anyhow!(message)),
+            None => Err(anyhow!("MockHttpClient has no reply for {}", key)),
+        };
+        (reply, state.delay)
+    }
+
+    // A test that panicked while holding the lock should not hide later failures
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl HttpClient for MockHttpClient {
+    async fn send(&self, request: Request) -> Result<Response> {
+        self.answer(request).await
+    }
+
+    async fn send_streaming(
+        &self,
+        request: Request,
+        on_chunk: &mut (dyn FnMut(Bytes) + Send),
+    ) -> Result<Response> {
+        let mut response = self.answer(request).await?;
+        let body = std::mem::take(&mut response.body_bytes);
+        if !body.is_empty() {
+            on_chunk(Bytes::from(body));
+        }
+        Ok(response)
+    }
+
+    async fn connect(&self, url: &Url) -> Result<ConnectionInfo> {
+        let peer = url
+            .0
+            .authority()
+            .map(|authority| authority.to_string())
+            .unwrap_or_default();
+        Ok(ConnectionInfo {
+            peer,
+            tls: None,
+            elapsed: Duration::ZERO,
+        })
+    }
+}
+
+fn route_key(method: &Method, url: &str) -> String {
+    format!("{} {}", method.as_str(), url)
+}
+
+// Matches URLs the way requests format them, so `http://host` and `http://host/` agree
+fn normalize(url: &str) -> String {
+    Url::new(url)
+        .map(|url| url.as_str())
+        .unwrap_or_else(|_| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(url: &str) -> Request {
+        Request {
+            method: Method::Get,
+            url: Url::new(url).unwrap(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn replies_in_order_and_repeats_the_last() {
+        let mock = MockHttpClient::new()
+            .on(
+                Method::Get,
+                "http://api.test",
+                MockHttpClient::reply(503, ""),
+            )
+            .on(
+                Method::Get,
+                "http://api.test",
+                MockHttpClient::reply(200, "ok"),
+            );
+
+        let statuses = [
+            mock.send(get("http://api.test/")).await.unwrap().status,
+            mock.send(get("http://api.test/")).await.unwrap().status,
+            mock.send(get("http://api.test/")).await.unwrap().status,
+        ];
+        assert_eq!(statuses.map(|status| status.as_u16()), [503, 200, 200]);
+        mock.assert_called(Method::Get, "http://api.test/", 3);
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_and_queued_errors_fail() {
+        let mock = MockHttpClient::new().on_error(Method::Get, "http://api.test/down", "reset");
+
+        let queued = mock.send(get("http://api.test/down")).await.unwrap_err();
+        assert_eq!(queued.to_string(), "reset");
+        let unknown = mock.send(get("http://api.test/other")).await.unwrap_err();
+        assert!(
+            unknown
+                .to_string()
+                .contains("no reply for GET http://api.test/other")
+        );
+        assert_eq!(mock.calls().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn streaming_hands_over_the_body() {
+        let mock = MockHttpClient::new().on(
+            Method::Get,
+            "http://api.test/file",
+            MockHttpClient::reply(200, "chunk"),
+        );
+
+        let mut received = Vec::new();
+        let response = mock
+            .send_streaming(get("http://api.test/file"), &mut |chunk| {
+                received.extend_from_slice(&chunk)
+            })
+            .await
+            .unwrap();
+        assert_eq!(received, b"chunk");
+        assert!(response.body_bytes.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replies_arrive_after_the_delay() {
+        let mock = MockHttpClient::new()
+            .on(
+                Method::Get,
+                "http://api.test",
+                MockHttpClient::reply(200, ""),
+            )
+            .with_delay(Duration::from_secs(3));
+
+        let started = tokio::time::Instant::now();
+        mock.send(get("http://api.test/")).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(3));
+    }
+}
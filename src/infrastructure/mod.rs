@@ -1 +1,5 @@
 pub mod http_client;
+pub mod logging;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_client;
+pub mod transcript;
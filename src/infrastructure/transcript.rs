@@ -0,0 +1,218 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Which way a chunk of bytes travelled on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A chunk of bytes observed on the connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEvent {
+    /// Which connection carried the bytes, numbered from 0 in the order they were opened;
+    /// transcripts recorded before connections were numbered are all connection 0
+    #[serde(default)]
+    pub connection: usize,
+    pub direction: Direction,
+    /// Microseconds since recording started, from a monotonic clock
+    pub elapsed_micros: u64,
+    /// The bytes, base64-encoded
+    pub data: String,
+}
+
+/// Full record of the bytes exchanged during a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WireTranscript {
+    pub events: Vec<TranscriptEvent>,
+}
+
+impl WireTranscript {
+    /// Loads a transcript previously written with `save`
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read transcript '{}': {}", path, e))?;
+        serde_json::from_str(&contents).map_err(|e| anyhow!("Invalid transcript '{}': {}", path, e))
+    }
+
+    /// Writes the transcript as pretty-printed JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!("Failed to serialize transcript: {}", e))?;
+        std::fs::write(path, contents)
+            .map_err(|e| anyhow!("Failed to write transcript '{}': {}", path, e))
+    }
+
+    /// Number of connections with recorded traffic
+    pub fn connection_count(&self) -> usize {
+        self.events
+            .iter()
+            .map(|e| e.connection + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Chunks received on one connection, with their offset from the first
+    /// bytes sent on it, so they can be replayed with the original timing
+    pub fn received_chunks(&self, connection: usize) -> Result<Vec<(Duration, Vec<u8>)>> {
+        let events = || {
+            self.events
+                .iter()
+                .filter(move |e| e.connection == connection)
+        };
+        let start = events()
+            .find(|e| e.direction == Direction::Sent)
+            .map(|e| e.elapsed_micros)
+            .unwrap_or(0);
+
+        events()
+            .filter(|e| e.direction == Direction::Received)
+            .map(|event| {
+                let chunk = BASE64
+                    .decode(&event.data)
+                    .map_err(|e| anyhow!("Invalid transcript data: {}", e))?;
+                let offset = Duration::from_micros(event.elapsed_micros.saturating_sub(start));
+                Ok((offset, chunk))
+            })
+            .collect()
+    }
+}
+
+/// Shared handle that connections use to append to a transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptRecorder {
+    start: Instant,
+    transcript: Arc<Mutex<WireTranscript>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl Default for TranscriptRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TranscriptRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            transcript: Arc::new(Mutex::new(WireTranscript::default())),
+            connections: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Numbers a new connection; its bytes are recorded under the returned index
+    pub fn open_connection(&self) -> usize {
+        self.connections.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Appends a chunk of bytes, timestamped relative to the recorder's creation
+    pub fn record(&self, connection: usize, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        let event = TranscriptEvent {
+            connection,
+            direction,
+            elapsed_micros: self.start.elapsed().as_micros() as u64,
+            data: BASE64.encode(data),
+        };
+        if let Ok(mut transcript) = self.transcript.lock() {
+            transcript.events.push(event);
+        }
+    }
+
+    /// Returns a copy of everything recorded so far
+    pub fn snapshot(&self) -> WireTranscript {
+        self.transcript
+            .lock()
+            .map(|transcript| transcript.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(
+        connection: usize,
+        direction: Direction,
+        elapsed_micros: u64,
+        data: &[u8],
+    ) -> TranscriptEvent {
+        TranscriptEvent {
+            connection,
+            direction,
+            elapsed_micros,
+            data: BASE64.encode(data),
+        }
+    }
+
+    #[test]
+    fn recorder_numbers_connections_and_skips_empty_chunks() {
+        let recorder = TranscriptRecorder::new();
+        let first = recorder.open_connection();
+        let second = recorder.clone().open_connection();
+        recorder.record(first, Direction::Sent, b"GET / HTTP/1.1\r\n\r\n");
+        recorder.record(second, Direction::Received, b"");
+        recorder.record(second, Direction::Received, b"HTTP/1.1 200 OK\r\n\r\n");
+
+        let transcript = recorder.snapshot();
+        assert_eq!((first, second), (0, 1));
+        assert_eq!(transcript.events.len(), 2);
+        assert_eq!(transcript.connection_count(), 2);
+        let received: Vec<Vec<u8>> = transcript
+            .received_chunks(second)
+            .unwrap()
+            .into_iter()
+            .map(|(_, chunk)| chunk)
+            .collect();
+        assert_eq!(received, [b"HTTP/1.1 200 OK\r\n\r\n".to_vec()]);
+    }
+
+    #[test]
+    fn received_chunks_are_offset_from_the_first_send_on_their_connection() {
+        let transcript = WireTranscript {
+            events: vec![
+                event(0, Direction::Sent, 100, b"req1"),
+                event(0, Direction::Received, 350, b"res"),
+                event(1, Direction::Sent, 1_000, b"req2"),
+                event(0, Direction::Received, 400, b"ponse"),
+                event(1, Direction::Received, 1_500, b"other"),
+            ],
+        };
+        assert_eq!(
+            transcript.received_chunks(0).unwrap(),
+            vec![
+                (Duration::from_micros(250), b"res".to_vec()),
+                (Duration::from_micros(300), b"ponse".to_vec()),
+            ]
+        );
+        assert_eq!(
+            transcript.received_chunks(1).unwrap(),
+            vec![(Duration::from_micros(500), b"other".to_vec())]
+        );
+        assert!(transcript.received_chunks(2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn transcripts_without_connection_numbers_load_as_connection_zero() {
+        let json =
+            r#"{"events": [{"direction": "received", "elapsed_micros": 5, "data": "aGk="}]}"#;
+        let transcript: WireTranscript = serde_json::from_str(json).unwrap();
+        assert_eq!(transcript.connection_count(), 1);
+        assert_eq!(
+            transcript.received_chunks(0).unwrap(),
+            vec![(Duration::from_micros(5), b"hi".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Hurl: Rust-powered HTTP client that hits hard
+//!
+//! The library exposes the same layers the `hurl` binary is built from, so
+//! requests can be sent and chained from Rust code as well as the command line.
+
+pub mod application;
+pub mod domain;
+pub mod infrastructure;
+pub mod presentation;
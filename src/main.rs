@@ -1,12 +1,15 @@
-mod application;
-mod domain;
-mod infrastructure;
-mod presentation;
-
+use anyhow::Result;
 use clap::Parser;
-use crate::application::services::HttpRequestService;
-use crate::infrastructure::http_client::HyperHttpClient;
-use crate::presentation::cli::Cli;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use hurl::application::host_headers::HostHeaders;
+use hurl::application::services::HttpRequestService;
+use hurl::application::summary::SummaryRecorder;
+use hurl::infrastructure::http_client::{ClientOptions, HyperHttpClient};
+use hurl::infrastructure::logging;
+use hurl::infrastructure::transcript::{TranscriptRecorder, WireTranscript};
+use hurl::presentation::cli::Cli;
 
 /// Hurl: Rust-powered HTTP client that hits hard
 ///
@@ -16,11 +19,79 @@ use crate::presentation::cli::Cli;
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
-    let http_client = HyperHttpClient::new();
-    let request_service = HttpRequestService::new(Box::new(http_client));
 
-    if let Err(err) = cli.run(&request_service).await {
+    if let Err(err) = run(cli).await {
         eprintln!("{}", err);
         std::process::exit(1);
     }
 }
+
+async fn run(cli: Cli) -> Result<()> {
+    logging::init(cli.log_level.as_deref())?;
+
+    let options = client_options(&cli)?;
+    let recorder = options.transcript.clone();
+    let http_client = HyperHttpClient::new(options);
+    let mut request_service = HttpRequestService::new(Box::new(http_client));
+    if let Some(path) = &cli.host_config {
+        request_service = request_service.with_host_headers(HostHeaders::load(path)?);
+    }
+    if let Some(credentials) = cli.oauth2_credentials() {
+        // The token endpoint gets a plain client, not the one shaped by the
+        // transport flags for the user's request
+        let token_client = HyperHttpClient::new(ClientOptions::default());
+        request_service = request_service.with_oauth2(credentials, Box::new(token_client));
+    }
+    // --abort-on-redirect needs the 3xx response itself, so nothing is followed
+    let max_redirects = if cli.abort_on_redirect { 0 } else { cli.max_redirects };
+    request_service = request_service.with_max_redirects(max_redirects);
+    if let Some(timeout) = cli.timeout {
+        request_service = request_service.with_timeout(timeout);
+    }
+    let summary = cli.summary_json.as_ref().map(|_| SummaryRecorder::new());
+    if let Some(summary) = &summary {
+        request_service = request_service.with_summary(summary.clone());
+    }
+
+    let result = cli.run(&request_service).await;
+
+    // Save the transcript even when the request failed; that is when it helps most
+    if let (Some(path), Some(recorder)) = (&cli.trace_file, recorder) {
+        recorder.snapshot().save(path)?;
+    }
+    if let (Some(path), Some(summary)) = (&cli.summary_json, summary) {
+        summary.summary().save(path)?;
+    }
+    result
+}
+
+/// Builds the transport settings for the HTTP client from CLI flags
+fn client_options(cli: &Cli) -> Result<ClientOptions> {
+    let replay = match &cli.replay_transcript {
+        Some(path) => Some(Arc::new(WireTranscript::load(path)?)),
+        None => None,
+    };
+
+    Ok(ClientOptions {
+        max_headers: cli.max_headers,
+        request_target: cli.request_target,
+        discard_body: cli.no_body,
+        tcp_nodelay: !cli.no_tcp_nodelay,
+        keepalive_time: cli.keepalive_time.map(Duration::from_secs),
+        title_case_headers: cli.title_case_headers,
+        header_case_sensitive: cli.header_case_sensitive,
+        http3: cli.http3,
+        connect_retries: cli.connect_retries,
+        buffer_size: cli.buffer_size,
+        sni: cli.sni.clone(),
+        show_cert_on_error: cli.show_cert_on_error,
+        host_header: !cli.no_host_header,
+        header_order: cli.header_order.clone(),
+        transfer_gzip: cli.transfer_gzip,
+        chunk_size: cli.chunk_size.map(NonZeroUsize::get),
+        trailers: cli.request_trailers()?,
+        transcript: cli.trace_file.as_ref().map(|_| TranscriptRecorder::new()),
+        replay,
+        replay_speed: cli.replay_speed.or(cli.replay_delay.then_some(1.0)),
+    })
+}
@@ -1,12 +1,37 @@
-use crate::application::services::HttpRequestService;
-use crate::domain::entities::{Method, Request};
-use crate::domain::value_objects::{JsonBody, Url};
+use crate::application::assertions::{
+    Condition, CookieExpectation, ResponseAssertions, StatusPattern,
+};
+use crate::application::comparison::diff_responses;
+use crate::application::fuzz::FieldFuzzer;
+use crate::application::oauth2::ClientCredentials;
+use crate::application::services::{HttpRequestService, RequestValidator};
+use crate::application::snippets::{Snippet, SnippetStore};
+use crate::domain::entities::{Body, Method, Request, Response, basic_authorization};
+use crate::domain::json_path::JsonPath;
+use crate::domain::template::Template;
+use crate::domain::value_objects::{FileBody, FormBody, JsonBody, RawBody, Url};
+use crate::infrastructure::http_client::{DEFAULT_MAX_HEADERS, MIN_BUFFER_SIZE, RequestTarget};
+use crate::presentation::openapi::request_example;
+use crate::presentation::output::{
+    DEFAULT_JSON_INDENT, JsonStyle, OutputOptions, PrintSections, encode_body, output_encoding,
+    print_body_counts, print_connection_info, print_differences, print_json_line,
+    print_request_body, print_request_headers, print_response_body, print_selected_headers,
+    print_tls_info, print_trailers,
+};
 use anyhow::{Result, anyhow};
-use clap::Parser;
+use clap::{ArgGroup, Parser};
 use colored::Colorize;
-use serde_json::Value;
 use std::collections::HashMap;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tracing::debug;
+
+/// Redirects followed before giving up, like curl's --max-redirs with -L
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
 
 /// CLI configuration for Hurl
 #[derive(Parser, Debug)]
@@ -16,22 +41,109 @@ use std::str::FromStr;
     author = "Anthony Lombardi <me@t0nylombardi.com>"
 )]
 #[command(about = "Hurl: Rust-powered requests that hit hard", long_about = None)]
+#[command(group(ArgGroup::new("body_source").multiple(false)))]
 pub struct Cli {
     /// The URL to send the request to
-    pub url: String,
+    #[arg(required_unless_present_any = ["stdin_each", "snippet"])]
+    pub url: Option<String>,
+
+    /// HTTP method (GET, POST, PUT, DELETE, etc.); defaults to POST with a body, GET otherwise
+    #[arg(short, long)]
+    pub method: Option<String>,
 
-    /// HTTP method (GET, POST, PUT, DELETE, etc.)
-    #[arg(short, long, default_value = "GET")]
-    pub method: String,
+    /// Send the request as POST with the real method in an X-HTTP-Method-Override header
+    #[arg(long = "method-override")]
+    pub method_override: bool,
 
     /// Headers in the format "Key: Value"
     #[arg(short = 'H', long = "header")]
     pub headers: Vec<String>,
 
+    /// Query parameter in the format "key=value", percent-encoded and appended to the URL
+    #[arg(long = "query")]
+    pub query: Vec<String>,
+
+    /// Send these headers first, in this order, including auto-added ones like Host (comma-separated)
+    #[arg(long = "header-order", value_delimiter = ',')]
+    pub header_order: Vec<String>,
+
+    /// Don't add the automatic Host header (for testing how servers handle its absence)
+    #[arg(long = "no-host-header")]
+    pub no_host_header: bool,
+
     /// Request body (usually JSON)
-    #[arg(short = 'd', long = "data")]
+    #[arg(short = 'd', long = "data", group = "body_source")]
     pub body: Option<String>,
 
+    /// Request body sent literally, never read from a file even if it starts with @
+    #[arg(long = "data-raw", group = "body_source")]
+    pub data_raw: Option<String>,
+
+    /// Read the request body from a template file with {{var}} placeholders
+    #[arg(long = "data-template", group = "body_source")]
+    pub data_template: Option<String>,
+
+    /// Read the request body (JSON) from the system clipboard
+    #[arg(long = "data-clipboard", group = "body_source")]
+    pub data_clipboard: bool,
+
+    /// Upload a file as the request body, streamed from disk (PUT by default)
+    #[arg(short = 'T', long = "upload-file", group = "body_source")]
+    pub upload_file: Option<String>,
+
+    /// Stream the request body from a named pipe as it is written, until the writer closes it
+    #[arg(long = "data-fifo", group = "body_source")]
+    pub data_fifo: Option<String>,
+
+    /// Form field in the format "key=value", sent as application/x-www-form-urlencoded
+    #[arg(long = "form", group = "body_source")]
+    pub form: Vec<String>,
+
+    /// Template variables in the format "key=value"
+    #[arg(long = "var")]
+    pub vars: Vec<String>,
+
+    /// Save the method, URL, -H headers and -d body (placeholders kept) as a named snippet, without sending
+    #[arg(
+        long = "save-snippet",
+        conflicts_with_all = [
+            "snippet", "stdin_each", "data_template", "upload_file", "data_fifo",
+            "data_clipboard", "data_hex", "data_base64", "data_env", "edit_body", "form",
+            "query"
+        ]
+    )]
+    pub save_snippet: Option<String>,
+
+    /// Send a saved snippet, filling its placeholders from --var; -H adds headers, -d and a URL replace its own
+    #[arg(long = "snippet", conflicts_with = "stdin_each")]
+    pub snippet: Option<String>,
+
+    /// Snippet file to use instead of ~/.config/hurl/snippets.json
+    #[arg(long = "snippets-file")]
+    pub snippets_file: Option<String>,
+
+    /// Request body as raw bytes decoded from a hex string
+    #[arg(long = "data-hex", group = "body_source")]
+    pub data_hex: Option<String>,
+
+    /// Request body as raw bytes decoded from a base64 string
+    #[arg(long = "data-base64", group = "body_source")]
+    pub data_base64: Option<String>,
+
+    /// Read the request body (JSON) from the named environment variable
+    #[arg(long = "data-env", group = "body_source")]
+    pub data_env: Option<String>,
+
+    /// Compose the JSON body in $VISUAL or $EDITOR, starting from any -d/--data-raw content
+    #[arg(
+        long = "edit-body",
+        conflicts_with_all = [
+            "data_template", "upload_file", "data_fifo", "data_clipboard", "form",
+            "data_hex", "data_base64", "data_env", "stdin_each"
+        ]
+    )]
+    pub edit_body: bool,
+
     /// Enable verbose output
     #[arg(short, long)]
     pub verbose: bool,
@@ -40,9 +152,314 @@ pub struct Cli {
     #[arg(short, long)]
     pub output: Option<String>,
 
+    /// Transcode the body to this charset (e.g. latin1) when writing --output [default: the bytes as received]
+    #[arg(long = "output-charset", requires = "output")]
+    pub output_charset: Option<String>,
+
+    /// Print each response as a single-line JSON object (url, status, timing, body)
+    #[arg(
+        long = "output-jsonl",
+        conflicts_with_all = ["output", "header_out", "print", "count_lines", "count_bytes", "compare"]
+    )]
+    pub output_jsonl: bool,
+
+    /// Print only the value of this response header (repeatable)
+    #[arg(long = "header-out")]
+    pub header_out: Vec<String>,
+
+    /// Fail when a header requested with --header-out is missing
+    #[arg(long = "header-out-required", requires = "header_out")]
+    pub header_out_required: bool,
+
+    /// Print the number of lines in the response body instead of the body
+    #[arg(long = "count-lines")]
+    pub count_lines: bool,
+
+    /// Print the number of bytes in the response body instead of the body
+    #[arg(long = "count-bytes")]
+    pub count_bytes: bool,
+
+    /// Fail unless the response body is valid JSON
+    #[arg(long = "validate-json-response")]
+    pub validate_json_response: bool,
+
+    /// Number of spaces used to indent pretty-printed JSON
+    #[arg(long = "json-indent", default_value_t = DEFAULT_JSON_INDENT)]
+    pub json_indent: usize,
+
+    /// Print JSON responses on a single line
+    #[arg(long = "json-compact", conflicts_with = "json_indent")]
+    pub json_compact: bool,
+
+    /// Print at most this many bytes of each body; --output still writes it in full
+    #[arg(long = "max-display-bytes")]
+    pub max_display_bytes: Option<usize>,
+
+    /// Print JSON responses as flattened `dotted.key = value` lines
+    #[arg(long)]
+    pub flatten: bool,
+
+    /// Write binary response bodies to stdout even when it is a terminal
+    #[arg(long = "force-binary-stdout")]
+    pub force_binary_stdout: bool,
+
+    /// Fail if the total request time exceeds this many milliseconds
+    #[arg(long = "expect-max-time")]
+    pub expect_max_time: Option<u64>,
+
+    /// Fail on a 3xx response instead of following it, reporting its status and Location
+    #[arg(long = "abort-on-redirect", conflicts_with = "max_redirects")]
+    pub abort_on_redirect: bool,
+
+    /// Follow at most this many redirects, failing beyond that; 0 returns the 3xx response as-is
+    #[arg(long = "max-redirects", default_value_t = DEFAULT_MAX_REDIRECTS)]
+    pub max_redirects: usize,
+
+    /// Fail unless the response sets this cookie, optionally with this value: NAME[=VALUE] (repeatable)
+    #[arg(long = "expect-cookie")]
+    pub expect_cookie: Vec<CookieExpectation>,
+
+    /// Re-send the request until this condition holds, e.g. '$.state == "done"' or 'status == 200'
+    #[arg(long = "repeat-until")]
+    pub repeat_until: Option<Condition>,
+
+    /// Milliseconds to wait between attempts with --repeat-until
+    #[arg(
+        long = "poll-interval",
+        default_value_t = 1000,
+        requires = "repeat_until"
+    )]
+    pub poll_interval: u64,
+
+    /// Fail a request that takes longer than this many seconds, connecting and reading included
+    #[arg(long = "timeout", value_parser = parse_seconds)]
+    pub timeout: Option<Duration>,
+
+    /// Give up on --repeat-until after this many seconds
+    #[arg(long = "poll-timeout", default_value_t = 60, requires = "repeat_until")]
+    pub poll_timeout: u64,
+
+    /// Re-send the request once per --fuzz-values entry with this JSON body field replaced, e.g. '$.user.age'
+    #[arg(
+        long = "fuzz-field",
+        requires = "fuzz_values",
+        conflicts_with_all = ["compare", "stdin_each", "repeat_until", "output", "openapi_example"]
+    )]
+    pub fuzz_field: Option<JsonPath>,
+
+    /// File with one substitute value per line for --fuzz-field (JSON values, or plain strings)
+    #[arg(long = "fuzz-values", requires = "fuzz_field")]
+    pub fuzz_values: Option<String>,
+
+    /// Sections to print: H request headers, B request body, h response headers, b response body
+    #[arg(long)]
+    pub print: Option<PrintSections>,
+
+    /// Send the same request to a second URL and report how the responses differ
+    #[arg(long)]
+    pub compare: Option<String>,
+
+    /// Header to leave out of --compare, e.g. Date (repeatable)
+    #[arg(long = "compare-ignore", requires = "compare")]
+    pub compare_ignore: Vec<String>,
+
+    /// Print or save the body only for these statuses, e.g. 2xx or 200,404; otherwise print just the status
+    #[arg(long = "body-on")]
+    pub body_on: Option<StatusPattern>,
+
+    /// Discard the response body without buffering or printing it
+    #[arg(long = "no-body", conflicts_with = "output")]
+    pub no_body: bool,
+
+    /// Send one request per stdin line as it arrives, using the line as the body or the URL
+    #[arg(
+        long = "stdin-each",
+        value_name = "body|url",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "body",
+        conflicts_with_all = ["compare", "output"]
+    )]
+    pub stdin_each: Option<StdinEach>,
+
+    /// With --stdin-each, copy this response header onto the following requests (repeatable)
+    #[arg(long = "reuse-header", requires = "stdin_each")]
+    pub reuse_header: Vec<String>,
+
+    /// Print the request as an OpenAPI example (YAML) instead of sending it
+    #[arg(long = "openapi-example", conflicts_with_all = ["compare", "stdin_each"])]
+    pub openapi_example: bool,
+
+    /// Only check that the request is valid (URL, method and body), without any network access
+    #[arg(
+        long = "validate-only",
+        conflicts_with_all = ["compare", "openapi_example", "connect_only", "fuzz_field"]
+    )]
+    pub validate_only: bool,
+
+    /// Only establish the connection (TCP, plus TLS for https) and report it, without sending a request
+    #[arg(
+        long = "connect-only",
+        conflicts_with_all = ["compare", "stdin_each", "repeat_until", "output"]
+    )]
+    pub connect_only: bool,
+
     /// Launch an interactive wizard
     #[arg(long)]
     pub wizard: bool,
+
+    /// Maximum number of response headers to accept
+    #[arg(long = "max-headers", default_value_t = DEFAULT_MAX_HEADERS)]
+    pub max_headers: usize,
+
+    /// Request-target form: origin, absolute, authority or asterisk
+    #[arg(long = "request-target", default_value = "origin")]
+    pub request_target: RequestTarget,
+
+    /// Send header names in Title-Case (e.g. X-Api-Key) instead of lowercase
+    #[arg(long = "title-case-headers")]
+    pub title_case_headers: bool,
+
+    /// Send the names of the headers you give exactly as typed (e.g. X-API-key)
+    #[arg(long = "header-case-sensitive")]
+    pub header_case_sensitive: bool,
+
+    /// Resend the request this many times after a failure or a 408, 429, 500, 502, 503 or 504,
+    /// waiting 250ms, then twice as long each time up to 30s
+    #[arg(long, default_value_t = 0)]
+    pub retry: u32,
+
+    /// Retry establishing the connection this many times when connecting fails, waiting 250ms, then twice as long each time up to 30s
+    #[arg(long = "connect-retries", default_value_t = 0)]
+    pub connect_retries: u32,
+
+    /// Leave Nagle's algorithm on (TCP_NODELAY is set by default) for bulk transfers
+    #[arg(long = "no-tcp-nodelay")]
+    pub no_tcp_nodelay: bool,
+
+    /// Enable TCP keepalive after this many idle seconds
+    #[arg(long = "keepalive-time")]
+    pub keepalive_time: Option<u64>,
+
+    /// Chunk size in bytes for streamed file bodies [default: 65536], also the most hyper
+    /// buffers while reading a response [default: about 400 KiB]; at least 8192
+    #[arg(long = "buffer-size", value_parser = parse_buffer_size)]
+    pub buffer_size: Option<usize>,
+
+    /// JSON file mapping hosts to default headers, applied below -H
+    #[arg(long = "host-config")]
+    pub host_config: Option<String>,
+
+    /// Basic auth credentials as "user:password"; prompts for the password when it is left out
+    #[arg(short = 'u', long = "user")]
+    pub user: Option<String>,
+
+    /// Send this token as "Authorization: Bearer <token>"
+    #[arg(long, conflicts_with_all = ["user", "token_env"])]
+    pub token: Option<String>,
+
+    /// Read the bearer token from this environment variable, keeping it out of shell history
+    #[arg(long = "token-env", conflicts_with = "user")]
+    pub token_env: Option<String>,
+
+    /// Print credentials in request headers instead of masking them
+    #[arg(long = "show-secrets")]
+    pub show_secrets: bool,
+
+    /// Fetch an OAuth2 client-credentials token from this URL and send it as a Bearer token
+    #[arg(
+        long = "oauth2-token-url",
+        requires_all = ["oauth2_client_id", "oauth2_client_secret"]
+    )]
+    pub oauth2_token_url: Option<String>,
+
+    /// OAuth2 client ID for --oauth2-token-url
+    #[arg(long = "oauth2-client-id", requires = "oauth2_token_url")]
+    pub oauth2_client_id: Option<String>,
+
+    /// OAuth2 client secret for --oauth2-token-url
+    #[arg(long = "oauth2-client-secret", requires = "oauth2_token_url")]
+    pub oauth2_client_secret: Option<String>,
+
+    /// Space-separated scopes to request with --oauth2-token-url
+    #[arg(long = "oauth2-scope", requires = "oauth2_token_url")]
+    pub oauth2_scope: Option<String>,
+
+    /// Send this hostname as TLS SNI instead of the URL host
+    #[arg(long)]
+    pub sni: Option<String>,
+
+    /// When the TLS handshake fails, show the certificate the server presented
+    #[arg(long = "show-cert-on-error")]
+    pub show_cert_on_error: bool,
+
+    /// Gzip the request body while streaming it (Transfer-Encoding: gzip, chunked)
+    #[arg(long = "transfer-gzip")]
+    pub transfer_gzip: bool,
+
+    /// Send the request body chunked, split into chunks of exactly this many bytes
+    #[arg(long = "chunk-size")]
+    pub chunk_size: Option<NonZeroUsize>,
+
+    /// Trailer in the format "Name: Value", sent after the body; makes the body chunked
+    #[arg(long = "trailer")]
+    pub trailers: Vec<String>,
+
+    /// Record the raw bytes exchanged, with timestamps, to this file
+    #[arg(long = "trace-file")]
+    pub trace_file: Option<String>,
+
+    /// Write a JSON summary of every request made (status, timing, bytes) to this file
+    #[arg(long = "summary-json")]
+    pub summary_json: Option<String>,
+
+    /// Answer the request from a recorded transcript instead of the network
+    #[arg(long = "replay-transcript")]
+    pub replay_transcript: Option<String>,
+
+    /// Reproduce the recorded timing between chunks when replaying
+    #[arg(long = "replay-delay", requires = "replay_transcript")]
+    pub replay_delay: bool,
+
+    /// Replay the recorded timing sped up by this factor (0.5 is half speed); implies --replay-delay
+    #[arg(long = "replay-speed", requires = "replay_transcript", value_parser = parse_speed)]
+    pub replay_speed: Option<f64>,
+
+    /// Send the request over HTTP/3 (QUIC) instead of HTTP/1.1; https only, and
+    /// experimental (needs a build with --features http3)
+    #[arg(
+        long,
+        conflicts_with_all = ["connect_only", "trace_file", "replay_transcript", "transfer_gzip"]
+    )]
+    pub http3: bool,
+
+    /// Log level or filter directive for diagnostics on stderr (overrides RUST_LOG)
+    #[arg(long = "log-level")]
+    pub log_level: Option<String>,
+}
+
+/// What each stdin line becomes in `--stdin-each` mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdinEach {
+    /// The line is the JSON request body
+    Body,
+    /// The line is the request URL
+    Url,
+}
+
+impl FromStr for StdinEach {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "body" => Ok(StdinEach::Body),
+            "url" => Ok(StdinEach::Url),
+            other => Err(anyhow!(
+                "Invalid --stdin-each mode '{}'. Use body or url",
+                other
+            )),
+        }
+    }
 }
 
 impl Cli {
@@ -52,65 +469,1262 @@ impl Cli {
             return Ok(());
         }
 
-        let url = Url::new(&self.url)?;
-        let method = Method::from_str(&self.method)?;
+        if self.connect_only {
+            let info = request_service
+                .check_connection(&Url::new(self.url()?)?)
+                .await?;
+            print_connection_info(&info);
+            return Ok(());
+        }
 
-        let headers = parse_headers(&self.headers)?;
-        let body = match &self.body {
-            Some(json) => Some(JsonBody::new(json)?),
+        if let Some(name) = &self.save_snippet {
+            return self.save_snippet(name);
+        }
+
+        let snippet = match &self.snippet {
+            Some(name) => {
+                let store = SnippetStore::open(self.snippets_file.as_deref())?;
+                Some(store.get(name)?.render(&parse_vars(&self.vars)?)?)
+            }
             None => None,
         };
 
-        let request = Request {
+        let mut headers = snippet
+            .as_ref()
+            .map(|snippet| snippet.headers.clone())
+            .unwrap_or_default();
+        headers.extend(parse_headers(&self.headers)?);
+        let has_authorization = headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("authorization"));
+        if let Some(user) = &self.user {
+            if has_authorization {
+                return Err(anyhow!(
+                    "Both --user and an Authorization header were given; use only one"
+                ));
+            }
+            let (user, pass) = match user.split_once(':') {
+                Some((user, pass)) => (user.to_string(), pass.to_string()),
+                None => (user.clone(), prompt_password(user)?),
+            };
+            headers.push((
+                "Authorization".to_string(),
+                basic_authorization(&user, &pass),
+            ));
+        }
+        if let Some(token) = self.bearer_token()? {
+            if has_authorization {
+                return Err(anyhow!(
+                    "Both --token and an Authorization header were given; use only one"
+                ));
+            }
+            headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        let body = match (self.resolve_body()?, &snippet) {
+            (Some(body), _) => Some(body),
+            (
+                None,
+                Some(Snippet {
+                    body: Some(json), ..
+                }),
+            ) => Some(Body::Json(JsonBody::new(json)?)),
+            (None, _) => None,
+        };
+        // Trailers follow the last chunk of a body, so without one there is nothing to carry them
+        if !self.trailers.is_empty() && body.is_none() && self.stdin_each != Some(StdinEach::Body) {
+            return Err(anyhow!(
+                "--trailer needs a request body to follow; add one with -d, --form or -T"
+            ));
+        }
+        // Reject an unknown charset before any request is sent
+        if let Some(label) = &self.output_charset {
+            output_encoding(label)?;
+        }
+
+        if let Some(mode) = self.stdin_each {
+            let lines = BufReader::new(tokio::io::stdin());
+            return self
+                .run_stdin_each(request_service, mode, headers, body, lines)
+                .await;
+        }
+
+        let url = match (&self.url, &snippet) {
+            (None, Some(snippet)) => snippet.url.as_str(),
+            _ => self.url()?,
+        };
+        let url = self.with_query(Url::new(&self.upload_url(url, &body))?)?;
+        let method = match (
+            &self.method,
+            snippet.as_ref().and_then(|s| s.method.as_ref()),
+        ) {
+            (None, Some(method)) => Method::from_str(method)?,
+            _ => self.resolve_method(&body)?,
+        };
+
+        let mut request = Request {
             method,
             url,
-            headers: headers.into_iter().collect(),
+            headers,
             body,
         };
+        if self.method_override {
+            request = request.with_method_override();
+        }
+
+        if self.validate_only {
+            return validate_request(&request);
+        }
+
+        if self.openapi_example {
+            println!("{}", request_example(&request));
+            return Ok(());
+        }
+
+        if let Some(compare_url) = &self.compare {
+            return self.compare(request_service, request, compare_url).await;
+        }
+
+        if let (Some(field), Some(path)) = (&self.fuzz_field, &self.fuzz_values) {
+            let fuzzer = FieldFuzzer::new(field.clone());
+            return self.fuzz(request_service, request, &fuzzer, path).await;
+        }
+
+        self.send_and_print(request_service, request).await?;
+        Ok(())
+    }
+
+    // The snippet keeps the raw flag values, so placeholders survive until --snippet renders them
+    fn save_snippet(&self, name: &str) -> Result<()> {
+        let snippet = Snippet {
+            method: self.method.clone(),
+            url: self.url()?.to_string(),
+            headers: parse_headers(&self.headers)?,
+            body: self.body.clone().or_else(|| self.data_raw.clone()),
+        };
+        let mut store = SnippetStore::open(self.snippets_file.as_deref())?;
+        store.save(name, snippet)?;
+        println!(
+            "{}",
+            format!("Saved snippet '{}' to {}", name, store.path().display()).green()
+        );
+        Ok(())
+    }
+
+    fn bearer_token(&self) -> Result<Option<String>> {
+        match (&self.token, &self.token_env) {
+            (Some(token), _) => Ok(Some(token.clone())),
+            (None, Some(name)) => std::env::var(name)
+                .map(Some)
+                .map_err(|_| anyhow!("Environment variable '{}' is not set", name)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn url(&self) -> Result<&str> {
+        self.url
+            .as_deref()
+            .ok_or_else(|| anyhow!("A URL is required"))
+    }
+
+    // Lines are read one at a time, so a fast producer waits on the previous
+    // response instead of being buffered in memory
+    async fn run_stdin_each(
+        &self,
+        request_service: &HttpRequestService,
+        mode: StdinEach,
+        headers: Vec<(String, String)>,
+        body: Option<Body>,
+        input: impl AsyncBufRead + Unpin,
+    ) -> Result<()> {
+        if mode == StdinEach::Body && body.is_some() {
+            return Err(anyhow!(
+                "--stdin-each body reads the body from stdin; drop the other body option"
+            ));
+        }
+
+        // Headers captured by --reuse-header; a later response carrying the
+        // header again replaces the value
+        let mut reused: Vec<(String, String)> = Vec::new();
+        let mut lines = input.lines();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| anyhow!("Failed to read stdin: {}", e))?
+        {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (url, body) = match mode {
+                StdinEach::Body => (
+                    self.url()?.to_string(),
+                    Some(Body::Json(JsonBody::new(line)?)),
+                ),
+                StdinEach::Url => (line.to_string(), body.clone()),
+            };
+
+            let mut request = Request {
+                method: self.resolve_method(&body)?,
+                url: self.with_query(Url::new(&self.upload_url(&url, &body))?)?,
+                headers: headers.clone(),
+                body,
+            };
+            if self.method_override {
+                request = request.with_method_override();
+            }
+            for (name, value) in &reused {
+                request = request.with_header(name, value);
+            }
+            // Stops at the first invalid line; its error is the one reported
+            if self.validate_only {
+                validate_request(&request)?;
+                continue;
+            }
+            let response = self.send_and_print(request_service, request).await?;
+
+            for name in &self.reuse_header {
+                if let Some(value) = response.header(name) {
+                    reused.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+                    reused.push((name.clone(), value.to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_and_print(
+        &self,
+        request_service: &HttpRequestService,
+        request: Request,
+    ) -> Result<Response> {
+        let sections = self.print_sections();
+        if sections.request_headers {
+            print_request_headers(
+                &request,
+                &self.header_order,
+                !self.no_host_header,
+                self.show_secrets,
+            );
+        }
+        if sections.request_body {
+            print_request_body(&request, self.max_display_bytes);
+        }
+
+        let url = request.url.as_str();
+        let response = match &self.repeat_until {
+            Some(condition) => self.poll(request_service, request, condition).await?,
+            None => request_service.send_with_retry(request, self.retry).await?,
+        };
+        if self.verbose
+            && let Some(tls) = &response.tls
+        {
+            print_tls_info(tls);
+        }
+
+        self.print_response(&url, &response, &sections)?;
+        Ok(response)
+    }
+
+    fn print_response(
+        &self,
+        url: &str,
+        response: &Response,
+        sections: &PrintSections,
+    ) -> Result<()> {
+        // The assertions are checked before anything is printed, so no output
+        // mode can skip them; a failing response is still printed in full
+        let verdict = self.assertions().check(response);
+
+        if self.output_jsonl {
+            print_json_line(url, response)?;
+            return verdict;
+        }
+
+        if !self.header_out.is_empty() {
+            print_selected_headers(response, &self.header_out, self.header_out_required)?;
+            return verdict;
+        }
 
-        let response = request_service.send_request(request).await?;
+        if sections.response_headers {
+            println!("{}", format!("Status: {}", response.status).cyan());
+            for (name, value) in &response.headers {
+                println!("{}", format!("{}: {}", name, value).cyan());
+            }
+            println!(
+                "{}",
+                format!(
+                    "Time: connect {}ms, total {}ms",
+                    response.timing.connect.as_millis(),
+                    response.timing.total.as_millis()
+                )
+                .cyan()
+            );
+        }
 
-        if self.verbose {
+        let body_allowed = self
+            .body_on
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches(response.status.as_u16()));
+        if sections.response_body && !body_allowed && !sections.response_headers {
             println!("{}", format!("Status: {}", response.status).cyan());
         }
 
-        if let Some(path) = &self.output {
-            std::fs::write(path, &response.body)?;
-            if self.verbose {
-                println!("Saved response to {}", path);
+        if sections.response_body && !self.no_body && body_allowed {
+            let counting = self.count_lines || self.count_bytes;
+            if let Some(path) = &self.output {
+                match &self.output_charset {
+                    Some(label) => {
+                        let bytes = encode_body(&response.text(), output_encoding(label)?)?;
+                        std::fs::write(path, bytes)?;
+                    }
+                    None => std::fs::write(path, &response.body_bytes)?,
+                }
+                if self.verbose {
+                    println!("Saved response to {}", path);
+                }
+            } else if !counting {
+                print_response_body(response, &self.output_options())?;
+            }
+
+            if counting {
+                print_body_counts(response, self.count_lines, self.count_bytes);
+            }
+        }
+
+        if sections.response_headers {
+            print_trailers(&response.trailers);
+        }
+
+        verdict
+    }
+
+    /// Request trailers given with --trailer
+    pub fn request_trailers(&self) -> Result<Vec<(String, String)>> {
+        parse_headers(&self.trailers)
+    }
+
+    /// OAuth2 client-credentials settings, when a token URL was given
+    pub fn oauth2_credentials(&self) -> Option<ClientCredentials> {
+        Some(ClientCredentials {
+            token_url: self.oauth2_token_url.clone()?,
+            client_id: self.oauth2_client_id.clone()?,
+            client_secret: self.oauth2_client_secret.clone()?,
+            scope: self.oauth2_scope.clone(),
+        })
+    }
+
+    // Re-sends the request until the condition holds, failing once the next
+    // attempt would start after the timeout
+    async fn poll(
+        &self,
+        request_service: &HttpRequestService,
+        request: Request,
+        condition: &Condition,
+    ) -> Result<Response> {
+        let interval = Duration::from_millis(self.poll_interval);
+        let deadline = Instant::now() + Duration::from_secs(self.poll_timeout);
+
+        for attempt in 1.. {
+            let response = request_service.send_request(request.clone()).await?;
+            if condition.is_met(&response) {
+                return Ok(response);
+            }
+            if Instant::now() + interval > deadline {
+                break;
             }
+            debug!(attempt, status = %response.status, "Condition not met, polling again");
+            tokio::time::sleep(interval).await;
+        }
+
+        Err(anyhow!(
+            "Condition '{}' not met within {}s",
+            condition,
+            self.poll_timeout
+        ))
+    }
+
+    // Without --print, show the response body plus its headers in verbose mode
+    fn print_sections(&self) -> PrintSections {
+        self.print.unwrap_or(PrintSections {
+            response_headers: self.verbose,
+            response_body: true,
+            ..PrintSections::default()
+        })
+    }
+
+    fn output_options(&self) -> OutputOptions {
+        let json_style = if self.json_compact {
+            JsonStyle::Compact
         } else {
-            print_body(&response.body)?;
+            JsonStyle::Pretty(self.json_indent)
+        };
+        OutputOptions {
+            json_style,
+            flatten: self.flatten,
+            max_display_bytes: self.max_display_bytes,
+            force_binary_stdout: self.force_binary_stdout,
         }
+    }
 
+    fn assertions(&self) -> ResponseAssertions {
+        ResponseAssertions {
+            valid_json: self.validate_json_response,
+            max_time: self.expect_max_time.map(Duration::from_millis),
+            cookies: self.expect_cookie.clone(),
+            no_redirect: self.abort_on_redirect,
+        }
+    }
+
+    async fn compare(
+        &self,
+        request_service: &HttpRequestService,
+        request: Request,
+        compare_url: &str,
+    ) -> Result<()> {
+        let secondary_request = request.with_url(Url::new(compare_url)?);
+
+        let primary = request_service.send_request(request).await?;
+        let secondary = request_service.send_request(secondary_request).await?;
+
+        let differences = diff_responses(&primary, &secondary, &self.compare_ignore);
+        print_differences(&differences);
+
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Responses differ in {} place(s)",
+                differences.len()
+            ))
+        }
+    }
+
+    // Every variant is sent even after a failed one, so the report covers all values
+    async fn fuzz(
+        &self,
+        request_service: &HttpRequestService,
+        request: Request,
+        fuzzer: &FieldFuzzer,
+        values_path: &str,
+    ) -> Result<()> {
+        // Build every variant first so a missing field fails before anything is sent
+        let values = FieldFuzzer::load_values(values_path)?;
+        let variants = values
+            .iter()
+            .map(|value| fuzzer.variant(&request, value))
+            .collect::<Result<Vec<_>>>()?;
+
+        for (value, variant) in values.iter().zip(variants) {
+            match request_service.send_request(variant).await {
+                Ok(response) if response.status.is_success() => {
+                    println!(
+                        "{}",
+                        format!("{} {}", response.status.as_u16(), value).green()
+                    )
+                }
+                Ok(response) => {
+                    println!(
+                        "{}",
+                        format!("{} {}", response.status.as_u16(), value).yellow()
+                    )
+                }
+                Err(err) => println!("{}", format!("ERR {}: {}", value, err).red()),
+            }
+        }
         Ok(())
     }
-}
 
-fn parse_headers(raw_headers: &[String]) -> Result<HashMap<String, String>> {
-    let mut headers = HashMap::new();
-    for raw in raw_headers {
-        let parts: Vec<&str> = raw.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            return Err(anyhow!(
-                "Invalid header format: '{}'. Use 'Key: Value'",
-                raw
-            ));
+    // An explicit --method always wins; otherwise infer it from the body like httpie,
+    // with file uploads defaulting to PUT like curl -T
+    fn resolve_method(&self, body: &Option<Body>) -> Result<Method> {
+        match (&self.method, body) {
+            (Some(method), _) => Method::from_str(method),
+            (None, Some(Body::File(_))) if self.upload_file.is_some() => Ok(Method::Put),
+            (None, Some(_)) => Ok(Method::Post),
+            (None, None) => Ok(Method::Get),
+        }
+    }
+
+    // Like curl -T, a URL ending in '/' gets the uploaded file's name appended
+    fn upload_url(&self, url: &str, body: &Option<Body>) -> String {
+        match body {
+            Some(Body::File(file)) if self.upload_file.is_some() && url.ends_with('/') => {
+                match file.file_name() {
+                    Some(name) => format!("{}{}", url, name),
+                    None => url.to_string(),
+                }
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    // Appends the --query parameters in the order they were given
+    fn with_query(&self, url: Url) -> Result<Url> {
+        self.query.iter().try_fold(url, |url, raw| {
+            let (key, value) = raw
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid query format: '{}'. Use 'key=value'", raw))?;
+            url.with_query_param(key, value)
+        })
+    }
+
+    fn resolve_body(&self) -> Result<Option<Body>> {
+        if let Some(path) = &self.data_template {
+            let source = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read template '{}': {}", path, e))?;
+            let vars = parse_vars(&self.vars)?;
+            let rendered = Template::new(&source).render(&vars)?;
+            return Ok(Some(Body::Json(JsonBody::new(&rendered)?)));
+        }
+        if let Some(path) = &self.upload_file {
+            return Ok(Some(Body::File(FileBody::new(path)?)));
+        }
+        if let Some(path) = &self.data_fifo {
+            return Ok(Some(Body::File(FileBody::fifo(path)?)));
+        }
+        if !self.form.is_empty() {
+            let fields = self
+                .form
+                .iter()
+                .map(|raw| {
+                    let (key, value) = raw.split_once('=').ok_or_else(|| {
+                        anyhow!("Invalid form field format: '{}'. Use 'key=value'", raw)
+                    })?;
+                    Ok((key.to_string(), value.to_string()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Some(Body::Form(FormBody::new(fields))));
+        }
+        if let Some(name) = &self.data_env {
+            let json = std::env::var(name)
+                .map_err(|_| anyhow!("Environment variable '{}' is not set", name))?;
+            return Ok(Some(Body::Json(JsonBody::new(&json)?)));
+        }
+        if self.data_clipboard {
+            return Ok(Some(Body::Json(JsonBody::new(&read_clipboard()?)?)));
+        }
+        if let Some(hex) = &self.data_hex {
+            return Ok(Some(Body::Raw(RawBody::from_hex(hex)?)));
+        }
+        if let Some(encoded) = &self.data_base64 {
+            return Ok(Some(Body::Raw(RawBody::from_base64(encoded)?)));
+        }
+
+        let inline = self.body.as_ref().or(self.data_raw.as_ref());
+        if self.edit_body {
+            let edited = edit_in_editor(inline.map(String::as_str).unwrap_or_default())?;
+            return Ok(Some(Body::Json(JsonBody::new(&edited)?)));
+        }
+        match inline {
+            Some(json) => Ok(Some(Body::Json(JsonBody::new(json)?))),
+            None => Ok(None),
         }
-        headers.insert(parts[0].trim().to_string(), parts[1].trim().to_string());
     }
-    Ok(headers)
 }
 
-fn print_body(body: &str) -> Result<()> {
-    match serde_json::from_str::<Value>(body) {
-        Ok(json) => println!(
-            "{}",
-            serde_json::to_string_pretty(&json)
-                .map_err(|e| anyhow!("Failed to format JSON: {}", e))?
-                .green()
-        ),
-        Err(_) => println!("{}", body.white()),
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> Result<String> {
+    let clipboard = arboard::Clipboard::new()
+        .map_err(|e| anyhow!("Failed to read from the clipboard: {}", e))?;
+    clipboard_text(clipboard)
+}
+
+/// A source of clipboard text, so tests can stand in for the system clipboard
+#[cfg(feature = "clipboard")]
+trait ClipboardSource {
+    fn text(&mut self) -> Result<String, arboard::Error>;
+}
+
+#[cfg(feature = "clipboard")]
+impl ClipboardSource for arboard::Clipboard {
+    fn text(&mut self) -> Result<String, arboard::Error> {
+        self.get_text()
     }
+}
+
+#[cfg(feature = "clipboard")]
+fn clipboard_text(mut clipboard: impl ClipboardSource) -> Result<String> {
+    clipboard
+        .text()
+        .map_err(|e| anyhow!("Failed to read from the clipboard: {}", e))
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn read_clipboard() -> Result<String> {
+    Err(anyhow!(
+        "Clipboard support is not enabled; rebuild Hurl with --features clipboard"
+    ))
+}
+
+fn validate_request(request: &Request) -> Result<()> {
+    RequestValidator::validate(request)?;
+    println!(
+        "{}",
+        format!(
+            "Valid: {} {}",
+            request.method.as_str(),
+            request.url.as_str()
+        )
+        .green()
+    );
     Ok(())
 }
+
+// Like git, $VISUAL wins over $EDITOR and vi is the last resort. The variable
+// may carry arguments, e.g. "code --wait".
+fn edit_in_editor(initial: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    edit_with(&editor, initial)
+}
+
+// Runs `editor` on a fresh temporary file holding `initial` and returns what it was saved as
+fn edit_with(editor: &str, initial: &str) -> Result<String> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow!("$EDITOR is empty; set it to your editor command"))?;
+
+    let (path, mut file) = create_body_file()?;
+    let written = file.write_all(initial.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&path);
+        return Err(anyhow!("Failed to write '{}': {}", path.display(), e));
+    }
+
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let status = status.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            anyhow!(
+                "Editor '{}' not found; set $EDITOR to your editor command",
+                program
+            )
+        }
+        _ => anyhow!("Failed to start editor '{}': {}", program, e),
+    })?;
+    if !status.success() {
+        return Err(anyhow!("Editor exited with {}; request not sent", status));
+    }
+
+    let edited = edited.map_err(|e| anyhow!("Failed to read the edited body: {}", e))?;
+    if edited.trim().is_empty() {
+        return Err(anyhow!(
+            "Request body is empty after editing; request not sent"
+        ));
+    }
+    Ok(edited)
+}
+
+// Reads without echo from the terminal. Piped or scripted runs have nobody to
+// ask, so they get an empty password, as curl does.
+fn prompt_password(user: &str) -> Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(String::new());
+    }
+
+    eprint!("Enter host password for user '{}': ", user);
+    let _ = std::io::stderr().flush();
+    terminal::enable_raw_mode().map_err(|e| anyhow!("Failed to read the password: {}", e))?;
+    let mut password = String::new();
+    let outcome = loop {
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+            Ok(_) => continue,
+            Err(e) => break Err(anyhow!("Failed to read the password: {}", e)),
+        };
+        match key.code {
+            KeyCode::Enter => break Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                break Err(anyhow!("Password prompt cancelled"));
+            }
+            KeyCode::Char(c) => password.push(c),
+            KeyCode::Backspace => {
+                password.pop();
+            }
+            _ => {}
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    eprintln!();
+    outcome.map(|_| password)
+}
+
+fn parse_seconds(raw: &str) -> Result<Duration> {
+    match raw.parse::<f64>() {
+        Ok(secs) if secs > 0.0 => Duration::try_from_secs_f64(secs)
+            .map_err(|_| anyhow!("Timeout of {}s is too large", raw)),
+        _ => Err(anyhow!("Timeout must be a positive number of seconds")),
+    }
+}
+
+// Creates a new file with an unpredictable name in the temp directory, readable only by
+// the current user. `create_new` refuses to open anything already there, so a file or
+// symlink planted at the path cannot be written through.
+fn create_body_file() -> Result<(PathBuf, std::fs::File)> {
+    use std::hash::{BuildHasher, RandomState};
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let state = RandomState::new();
+    let mut attempt = 0u32;
+    loop {
+        let suffix = state.hash_one((std::process::id(), attempt));
+        let path = std::env::temp_dir().join(format!("hurl-body-{:016x}.json", suffix));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempt < 16 => attempt += 1,
+            Err(e) => return Err(anyhow!("Failed to create '{}': {}", path.display(), e)),
+        }
+    }
+}
+
+fn parse_speed(raw: &str) -> Result<f64> {
+    match raw.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(anyhow!("Replay speed must be a positive number")),
+    }
+}
+
+fn parse_buffer_size(raw: &str) -> Result<usize> {
+    match raw.parse::<usize>() {
+        Ok(size) if size >= MIN_BUFFER_SIZE => Ok(size),
+        _ => Err(anyhow!(
+            "Buffer size must be a number of bytes, at least {}",
+            MIN_BUFFER_SIZE
+        )),
+    }
+}
+
+fn parse_vars(raw_vars: &[String]) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for raw in raw_vars {
+        let (key, value) = raw
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid variable format: '{}'. Use 'key=value'", raw))?;
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+// Headers keep the order they were given in, and a repeated name is sent repeatedly
+fn parse_headers(raw_headers: &[String]) -> Result<Vec<(String, String)>> {
+    raw_headers
+        .iter()
+        .map(|raw| {
+            let (key, value) = raw
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid header format: '{}'. Use 'Key: Value'", raw))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::mock_client::MockHttpClient;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(["hurl"].iter().chain(args)).unwrap()
+    }
+
+    fn cli_error(args: &[&str]) -> String {
+        Cli::try_parse_from(["hurl"].iter().chain(args))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn timeout_takes_positive_seconds() {
+        assert_eq!(
+            cli(&["--timeout", "1.5", "http://a.test"]).timeout,
+            Some(Duration::from_millis(1500))
+        );
+        assert!(
+            cli_error(&["--timeout", "0", "http://a.test"]).contains("must be a positive number")
+        );
+        assert!(
+            cli_error(&["--timeout", "1e300", "http://a.test"])
+                .contains("Timeout of 1e300s is too large")
+        );
+    }
+
+    #[test]
+    fn data_templates_are_read_from_the_file_and_rendered() {
+        let path = std::env::temp_dir().join(format!("hurl-template-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"id": {{id}}, "name": "{{ name }}"}"#).unwrap();
+        let path_arg = path.to_str().unwrap();
+
+        let body = cli(&[
+            "--data-template",
+            path_arg,
+            "--var",
+            "id=42",
+            "--var",
+            "name=box",
+            "http://a.test",
+        ])
+        .resolve_body();
+        let unset = cli(&[
+            "--data-template",
+            path_arg,
+            "--var",
+            "id=42",
+            "http://a.test",
+        ])
+        .resolve_body();
+        let not_json = cli(&[
+            "--data-template",
+            path_arg,
+            "--var",
+            "id=[",
+            "--var",
+            "name=box",
+            "http://a.test",
+        ])
+        .resolve_body();
+        std::fs::remove_file(&path).unwrap();
+
+        match body.unwrap() {
+            Some(Body::Json(json)) => assert_eq!(json.0, r#"{"id": 42, "name": "box"}"#),
+            other => panic!("expected a JSON body, got {:?}", other),
+        }
+        assert_eq!(
+            unset.unwrap_err().to_string(),
+            "Template variable 'name' is not set"
+        );
+        assert!(not_json.is_err());
+    }
+
+    #[cfg(feature = "clipboard")]
+    struct FakeClipboard(Option<&'static str>);
+
+    #[cfg(feature = "clipboard")]
+    impl ClipboardSource for FakeClipboard {
+        fn text(&mut self) -> Result<String, arboard::Error> {
+            self.0
+                .map(str::to_string)
+                .ok_or(arboard::Error::ContentNotAvailable)
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn clipboard_text_is_read_from_the_source() {
+        assert_eq!(
+            clipboard_text(FakeClipboard(Some(r#"{"id":1}"#))).unwrap(),
+            r#"{"id":1}"#
+        );
+        let error = clipboard_text(FakeClipboard(None)).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Failed to read from the clipboard: "),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn buffer_size_must_reach_the_minimum() {
+        assert_eq!(
+            cli(&["--buffer-size", "8192", "http://a.test"]).buffer_size,
+            Some(8192)
+        );
+        assert!(cli_error(&["--buffer-size", "4096", "http://a.test"]).contains("at least 8192"));
+        assert!(cli_error(&["--buffer-size", "big", "http://a.test"]).contains("at least 8192"));
+    }
+
+    #[test]
+    fn query_parameters_are_appended_in_order() {
+        let cli = cli(&[
+            "--query",
+            "q=rust lang",
+            "--query",
+            "page=2",
+            "http://a.test/s?x=1",
+        ]);
+        let url = cli
+            .with_query(Url::new("http://a.test/s?x=1").unwrap())
+            .unwrap();
+        assert_eq!(url.as_str(), "http://a.test/s?x=1&q=rust%20lang&page=2");
+    }
+
+    #[test]
+    fn query_parameters_need_a_key_and_value() {
+        let cli = cli(&["--query", "novalue", "http://a.test"]);
+        let error = cli
+            .with_query(Url::new("http://a.test").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid query format: 'novalue'. Use 'key=value'"
+        );
+    }
+
+    #[test]
+    fn form_fields_become_a_form_body() {
+        let cli = cli(&[
+            "--form",
+            "name=Jane Doe",
+            "--form",
+            "note=a=b",
+            "http://a.test",
+        ]);
+        let Some(Body::Form(form)) = cli.resolve_body().unwrap() else {
+            panic!("expected a form body");
+        };
+        assert_eq!(form.as_str(), "name=Jane+Doe&note=a%3Db");
+        assert!(matches!(
+            cli.resolve_method(&Some(Body::Form(form))),
+            Ok(Method::Post)
+        ));
+    }
+
+    #[test]
+    fn form_and_query_cannot_be_saved_as_snippets() {
+        for flag in ["--form", "--query"] {
+            let error = cli_error(&["--save-snippet", "s", flag, "a=b", "http://a.test"]);
+            assert!(error.contains("cannot be used with"), "{}", error);
+        }
+        assert!(
+            cli_error(&["--form", "a=b", "-d", "{}", "http://a.test"])
+                .contains("cannot be used with")
+        );
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn data_clipboard_needs_the_feature() {
+        let error = cli(&["--data-clipboard", "http://a.test"])
+            .resolve_body()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Clipboard support is not enabled; rebuild Hurl with --features clipboard"
+        );
+    }
+
+    #[test]
+    fn data_env_sends_the_variable_as_the_body() {
+        let name = format!("HURL_TEST_BODY_{}", std::process::id());
+        // SAFETY: the name is unique to this test, and no other test writes the environment
+        unsafe { std::env::set_var(&name, r#"{"id": 7}"#) };
+
+        let body = cli(&["--data-env", &name, "http://a.test"])
+            .resolve_body()
+            .unwrap();
+        let missing = cli(&["--data-env", "HURL_TEST_UNSET_BODY", "http://a.test"])
+            .resolve_body()
+            .unwrap_err();
+        unsafe { std::env::remove_var(&name) };
+
+        assert_eq!(
+            body.unwrap().as_bytes().unwrap(),
+            br#"{"id": 7}"#.as_slice()
+        );
+        assert_eq!(
+            missing.to_string(),
+            "Environment variable 'HURL_TEST_UNSET_BODY' is not set"
+        );
+    }
+
+    #[tokio::test]
+    async fn stdin_each_sends_one_request_per_line_in_order() {
+        let mock = MockHttpClient::new()
+            .on(
+                Method::Get,
+                "http://a.test/1",
+                MockHttpClient::reply(200, "one"),
+            )
+            .on(
+                Method::Get,
+                "http://a.test/2",
+                MockHttpClient::reply(200, "two"),
+            )
+            .on(
+                Method::Post,
+                "http://a.test/items",
+                MockHttpClient::reply(201, ""),
+            );
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+
+        cli(&["--stdin-each=url", "-H", "X-Run: 1"])
+            .run_stdin_each(
+                &service,
+                StdinEach::Url,
+                vec![("X-Run".to_string(), "1".to_string())],
+                None,
+                b"http://a.test/1\n\n  http://a.test/2\n".as_slice(),
+            )
+            .await
+            .unwrap();
+        cli(&["--stdin-each=body", "http://a.test/items"])
+            .run_stdin_each(
+                &service,
+                StdinEach::Body,
+                Vec::new(),
+                None,
+                b"{\"id\": 1}\n{\"id\": 2}\n".as_slice(),
+            )
+            .await
+            .unwrap();
+
+        let calls = mock.calls();
+        let sent: Vec<_> = calls
+            .iter()
+            .map(|call| {
+                let body = call.body.as_ref().and_then(|body| body.as_bytes());
+                (
+                    call.method.as_str(),
+                    call.url.as_str(),
+                    body.map(|body| String::from_utf8_lossy(body).into_owned()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                ("GET", "http://a.test/1".to_string(), None),
+                ("GET", "http://a.test/2".to_string(), None),
+                (
+                    "POST",
+                    "http://a.test/items".to_string(),
+                    Some(r#"{"id": 1}"#.to_string())
+                ),
+                (
+                    "POST",
+                    "http://a.test/items".to_string(),
+                    Some(r#"{"id": 2}"#.to_string())
+                ),
+            ]
+        );
+        assert!(
+            calls[..2]
+                .iter()
+                .all(|call| call.headers == [("X-Run".to_string(), "1".to_string())])
+        );
+    }
+
+    #[test]
+    fn data_raw_sends_at_signs_literally() {
+        let literal = cli(&["--data-raw", r#""@notafile""#, "http://a.test"])
+            .resolve_body()
+            .unwrap()
+            .unwrap();
+        assert_eq!(literal.as_bytes().unwrap(), br#""@notafile""#.as_slice());
+
+        // Validated as the JSON it was given, not looked up as a path
+        let bare = cli(&["--data-raw", "@notafile", "http://a.test"])
+            .resolve_body()
+            .unwrap_err();
+        assert!(bare.to_string().starts_with("Invalid JSON: "), "{bare}");
+    }
+
+    #[tokio::test]
+    async fn reuse_header_copies_a_response_header_onto_later_requests() {
+        let mut login = MockHttpClient::reply(200, "");
+        login
+            .headers
+            .push(("x-csrf-token".to_string(), "abc".to_string()));
+        let mut refreshed = MockHttpClient::reply(200, "");
+        refreshed
+            .headers
+            .push(("X-CSRF-Token".to_string(), "def".to_string()));
+        let mock = MockHttpClient::new()
+            .on(Method::Get, "http://a.test/login", login)
+            .on(Method::Get, "http://a.test/refresh", refreshed)
+            .on(
+                Method::Get,
+                "http://a.test/items",
+                MockHttpClient::reply(200, "[]"),
+            );
+        let service = HttpRequestService::new(Box::new(mock.clone()));
+
+        cli(&["--stdin-each=url", "--reuse-header", "X-CSRF-Token"])
+            .run_stdin_each(
+                &service,
+                StdinEach::Url,
+                Vec::new(),
+                None,
+                b"http://a.test/login\nhttp://a.test/refresh\nhttp://a.test/items\n".as_slice(),
+            )
+            .await
+            .unwrap();
+
+        let token = |call: &Request| {
+            call.headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("x-csrf-token"))
+                .map(|(_, value)| value.clone())
+        };
+        let tokens: Vec<_> = mock.calls().iter().map(token).collect();
+        assert_eq!(
+            tokens,
+            [None, Some("abc".to_string()), Some("def".to_string())]
+        );
+    }
+    #[test]
+    fn http3_cannot_be_combined_with_http1_only_flags() {
+        assert!(cli(&["--http3", "https://a.test"]).http3);
+        assert!(
+            cli_error(&["--http3", "--transfer-gzip", "https://a.test"])
+                .contains("--transfer-gzip")
+        );
+        assert!(
+            cli_error(&["--http3", "--trace-file", "t.json", "https://a.test"])
+                .contains("--trace-file")
+        );
+    }
+
+    #[test]
+    fn method_is_inferred_from_the_body() {
+        let json = Some(Body::Json(JsonBody::new("{}").unwrap()));
+        let method = |args: &[&str], body: &Option<Body>| cli(args).resolve_method(body).unwrap();
+        assert!(matches!(method(&["http://a.test"], &None), Method::Get));
+        assert!(matches!(method(&["http://a.test"], &json), Method::Post));
+        assert!(matches!(
+            method(&["-m", "PATCH", "http://a.test"], &json),
+            Method::Patch
+        ));
+    }
+
+    #[test]
+    fn uploads_to_a_directory_url_append_the_file_name() {
+        let path = std::env::temp_dir().join(format!("hurl-upload-{}.txt", std::process::id()));
+        std::fs::write(&path, "x").unwrap();
+        let path_arg = path.to_str().unwrap();
+        let cli = cli(&["-T", path_arg, "http://a.test/files/"]);
+        let body = cli.resolve_body();
+        std::fs::remove_file(&path).unwrap();
+
+        let body = body.unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            cli.upload_url("http://a.test/files/", &body),
+            format!("http://a.test/files/{}", name)
+        );
+        assert_eq!(
+            cli.upload_url("http://a.test/files/a.txt", &body),
+            "http://a.test/files/a.txt"
+        );
+        assert!(matches!(cli.resolve_method(&body), Ok(Method::Put)));
+    }
+
+    #[test]
+    fn bearer_token_comes_from_the_flag_or_the_environment() {
+        assert_eq!(
+            cli(&["--token", "abc", "http://a.test"])
+                .bearer_token()
+                .unwrap()
+                .as_deref(),
+            Some("abc")
+        );
+        assert_eq!(cli(&["http://a.test"]).bearer_token().unwrap(), None);
+
+        let missing = cli(&[
+            "--token-env",
+            "HURL_TEST_TOKEN_THAT_IS_NOT_SET",
+            "http://a.test",
+        ]);
+        assert_eq!(
+            missing.bearer_token().unwrap_err().to_string(),
+            "Environment variable 'HURL_TEST_TOKEN_THAT_IS_NOT_SET' is not set"
+        );
+    }
+
+    #[test]
+    fn token_flags_conflict_with_basic_auth() {
+        assert!(
+            cli_error(&["--token", "t", "-u", "a:b", "http://a.test"])
+                .contains("cannot be used with")
+        );
+        assert!(
+            cli_error(&["--token-env", "T", "-u", "a:b", "http://a.test"])
+                .contains("cannot be used with")
+        );
+        assert!(
+            cli_error(&["--token", "t", "--token-env", "T", "http://a.test"])
+                .contains("cannot be used with")
+        );
+    }
+
+    #[test]
+    fn query_is_accepted_as_a_method() {
+        let json = Some(Body::Json(JsonBody::new(r#"{"q":1}"#).unwrap()));
+        for name in ["QUERY", "query"] {
+            let method = cli(&["-m", name, "http://a.test"])
+                .resolve_method(&json)
+                .unwrap();
+            assert!(matches!(method, Method::Query));
+            assert_eq!(method.as_str(), "QUERY");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn editor_edits_a_fresh_private_file_that_is_removed_afterwards() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("hurl-editor-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let seen = dir.join("seen");
+        let script = dir.join("editor.sh");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$1\" > '{}'\nprintf '{{\"edited\":true}}' > \"$1\"\n",
+                seen.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let edited = edit_with(script.to_str().unwrap(), r#"{"edited":false}"#).unwrap();
+        let path = std::fs::read_to_string(&seen).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(edited, r#"{"edited":true}"#);
+        let path = std::path::Path::new(path.trim_end());
+        assert!(!path.exists(), "{} was left behind", path.display());
+        assert!(!path.ends_with(format!("hurl-body-{}.json", std::process::id())));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn body_files_are_new_and_readable_only_by_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (first, _) = create_body_file().unwrap();
+        let (second, _) = create_body_file().unwrap();
+        let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn a_failing_editor_sends_nothing() {
+        let error = edit_with("false", "{}").unwrap_err();
+        assert!(
+            error.to_string().starts_with("Editor exited with"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use colored::Colorize;
+
+// Elements that never have content or a closing tag
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// Elements whose content is not markup; it is kept as written
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+#[derive(Debug)]
+enum Token<'a> {
+    Start {
+        name: &'a str,
+        attributes: Vec<(&'a str, Option<&'a str>)>,
+        self_closing: bool,
+    },
+    End(&'a str),
+    Text(&'a str),
+    /// Content of a raw text element, up to its closing tag
+    Raw(&'a str),
+    /// Comment, doctype or other `<!...>` declaration, verbatim
+    Markup(&'a str),
+}
+
+/// Reindents HTML with two spaces per nesting level, optionally colouring
+/// tags, attributes and text for the terminal
+///
+/// Text is trimmed but otherwise kept; `<pre>`, `<textarea>`, `<script>` and
+/// `<style>` content keeps its own line breaks and relative indentation.
+///
+/// # Returns
+/// * `Some(String)` - The formatted document
+/// * `None` - If the markup could not be tokenized (an unterminated tag, quote or comment)
+pub fn format_html(html: &str, highlight: bool) -> Option<String> {
+    let tokens = tokenize(html)?;
+    let mut printer = Printer {
+        lines: Vec::new(),
+        highlight,
+    };
+    let mut open: Vec<&str> = Vec::new();
+    let mut index = 0;
+
+    while let Some(token) = tokens.get(index) {
+        index += 1;
+        let depth = open.len();
+        match token {
+            Token::Start {
+                name,
+                attributes,
+                self_closing,
+            } => {
+                let lowered = name.to_lowercase();
+                if open
+                    .last()
+                    .is_some_and(|last| implicitly_closed(last, &lowered))
+                {
+                    open.pop();
+                }
+                let depth = open.len();
+                let tag = printer.start_tag(name, attributes, *self_closing);
+                if *self_closing || VOID_ELEMENTS.contains(&lowered.as_str()) {
+                    printer.line(depth, tag);
+                    continue;
+                }
+
+                // Keep short elements such as <title>x</title> on one line
+                let inline = match (tokens.get(index), tokens.get(index + 1)) {
+                    (Some(Token::End(end)), _) if end.eq_ignore_ascii_case(name) => {
+                        Some((String::new(), 1))
+                    }
+                    (Some(Token::Text(text) | Token::Raw(text)), Some(Token::End(end)))
+                        if end.eq_ignore_ascii_case(name) && !text.trim().contains('\n') =>
+                    {
+                        Some((printer.text(text.trim()), 2))
+                    }
+                    _ => None,
+                };
+                if let Some((content, consumed)) = inline {
+                    let end_tag = printer.end_tag(name);
+                    printer.line(depth, format!("{}{}{}", tag, content, end_tag));
+                    index += consumed;
+                    continue;
+                }
+
+                printer.line(depth, tag);
+                open.push(name);
+            }
+            Token::End(name) => {
+                // A stray closing tag is printed but leaves the nesting alone
+                if let Some(position) = open
+                    .iter()
+                    .rposition(|open| open.eq_ignore_ascii_case(name))
+                {
+                    open.truncate(position);
+                }
+                let tag = printer.end_tag(name);
+                printer.line(open.len(), tag);
+            }
+            Token::Text(text) => {
+                for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                    let text = printer.text(line);
+                    printer.line(depth, text);
+                }
+            }
+            Token::Raw(raw) => printer.raw(depth, raw),
+            Token::Markup(markup) => {
+                let markup = printer.markup(markup.trim());
+                printer.line(depth, markup);
+            }
+        }
+    }
+
+    Some(printer.lines.join("\n"))
+}
+
+// Sibling elements whose end tag may be omitted, e.g. `<li>one<li>two`
+fn implicitly_closed(open: &str, starting: &str) -> bool {
+    let open = open.to_lowercase();
+    match starting {
+        "li" | "p" | "tr" | "option" => open == starting,
+        "td" | "th" => matches!(open.as_str(), "td" | "th"),
+        "dt" | "dd" => matches!(open.as_str(), "dt" | "dd"),
+        _ => false,
+    }
+}
+
+struct Printer {
+    lines: Vec<String>,
+    highlight: bool,
+}
+
+impl Printer {
+    fn line(&mut self, depth: usize, text: String) {
+        self.lines.push(format!("{}{}", "  ".repeat(depth), text));
+    }
+
+    fn start_tag(
+        &self,
+        name: &str,
+        attributes: &[(&str, Option<&str>)],
+        self_closing: bool,
+    ) -> String {
+        let mut tag = self.punctuation("<");
+        tag.push_str(&self.tag_name(name));
+        for (attribute, value) in attributes {
+            tag.push(' ');
+            tag.push_str(&self.paint(attribute, |text| text.cyan().to_string()));
+            if let Some(value) = value {
+                tag.push_str(&self.punctuation("="));
+                tag.push_str(&self.paint(value, |text| text.green().to_string()));
+            }
+        }
+        tag.push_str(&self.punctuation(if self_closing { " />" } else { ">" }));
+        tag
+    }
+
+    fn end_tag(&self, name: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.punctuation("</"),
+            self.tag_name(name),
+            self.punctuation(">")
+        )
+    }
+
+    // Raw content keeps its own line structure, shifted to sit under its tag
+    fn raw(&mut self, depth: usize, raw: &str) {
+        let lines: Vec<&str> = raw.trim_matches('\n').lines().collect();
+        let indent = lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        for line in lines {
+            let line = line.get(indent..).unwrap_or_else(|| line.trim_start());
+            let text = self.text(line.trim_end());
+            self.line(depth, text);
+        }
+    }
+
+    fn tag_name(&self, name: &str) -> String {
+        self.paint(name, |text| text.blue().bold().to_string())
+    }
+
+    fn punctuation(&self, text: &str) -> String {
+        self.paint(text, |text| text.blue().to_string())
+    }
+
+    fn text(&self, text: &str) -> String {
+        self.paint(text, |text| text.white().to_string())
+    }
+
+    fn markup(&self, text: &str) -> String {
+        self.paint(text, |text| text.bright_black().to_string())
+    }
+
+    fn paint(&self, text: &str, colour: impl Fn(&str) -> String) -> String {
+        if self.highlight {
+            colour(text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// A `<` that does not start a tag (e.g. `a < b`) is treated as text
+fn tokenize(html: &str) -> Option<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = find_tag(rest) else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->")? + 3;
+            tokens.push(Token::Markup(&rest[..end]));
+            rest = &rest[end..];
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let end = rest.find('>')? + 1;
+            tokens.push(Token::Markup(&rest[..end]));
+            rest = &rest[end..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>')?;
+            tokens.push(Token::End(after[..end].trim()));
+            rest = &after[end + 1..];
+        } else {
+            let (token, after) = start_tag(&rest[1..])?;
+            rest = after;
+            if let Token::Start {
+                name,
+                self_closing: false,
+                ..
+            } = token
+                && RAW_TEXT_ELEMENTS.contains(&name.to_lowercase().as_str())
+            {
+                // ASCII lowercasing keeps byte offsets valid for slicing `rest`
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&format!("</{}", name.to_ascii_lowercase()))?;
+                tokens.push(token);
+                if !rest[..end].trim().is_empty() {
+                    tokens.push(Token::Raw(&rest[..end]));
+                }
+                rest = &rest[end..];
+                continue;
+            }
+            tokens.push(token);
+        }
+    }
+
+    Some(tokens)
+}
+
+// Byte offset of the next `<` that opens a tag, comment or declaration
+fn find_tag(text: &str) -> Option<usize> {
+    text.match_indices('<')
+        .map(|(index, _)| index)
+        .find(|&index| {
+            text[index + 1..]
+                .chars()
+                .next()
+                .is_some_and(|next| next.is_ascii_alphabetic() || matches!(next, '/' | '!' | '?'))
+        })
+}
+
+// Parses `name attr="value" ...>` after the `<`, returning the rest of the input
+fn start_tag(text: &str) -> Option<(Token<'_>, &str)> {
+    let name_end = text
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(text.len());
+    let name = &text[..name_end];
+    let mut rest = &text[name_end..];
+    let mut attributes = Vec::new();
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            let token = Token::Start {
+                name,
+                attributes,
+                self_closing: true,
+            };
+            return Some((token, after));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            let token = Token::Start {
+                name,
+                attributes,
+                self_closing: false,
+            };
+            return Some((token, after));
+        }
+        if rest.is_empty() {
+            return None;
+        }
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+
+        let attribute_end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        let attribute = &rest[..attribute_end];
+        rest = rest[attribute_end..].trim_start();
+
+        let Some(after) = rest.strip_prefix('=') else {
+            attributes.push((attribute, None));
+            continue;
+        };
+        let after = after.trim_start();
+        let value_end = match after.chars().next() {
+            Some(quote @ ('"' | '\'')) => after[1..].find(quote)? + 2,
+            _ => after
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .unwrap_or(after.len()),
+        };
+        attributes.push((attribute, Some(&after[..value_end])));
+        rest = &after[value_end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_is_indented_and_short_elements_stay_inline() {
+        let html = "<!DOCTYPE html><html><head><title>Hi</title><meta charset=utf-8></head>\
+                    <body><p class=\"x\">one\n two</p><br/></body></html>";
+        assert_eq!(
+            format_html(html, false).unwrap(),
+            "<!DOCTYPE html>\n<html>\n  <head>\n    <title>Hi</title>\n    <meta charset=utf-8>\n  \
+             </head>\n  <body>\n    <p class=\"x\">\n      one\n      two\n    </p>\n    <br />\n  \
+             </body>\n</html>"
+        );
+    }
+
+    #[test]
+    fn omitted_end_tags_close_their_siblings() {
+        assert_eq!(
+            format_html("<ul><li>one<li>two</ul>", false).unwrap(),
+            "<ul>\n  <li>\n    one\n  <li>\n    two\n</ul>"
+        );
+    }
+
+    #[test]
+    fn raw_text_elements_keep_their_content() {
+        let formatted = format_html("<div><pre>a\n  b</pre></div>", false).unwrap();
+        assert!(formatted.contains("a\n"), "{}", formatted);
+        assert!(formatted.contains("  b"), "{}", formatted);
+    }
+
+    #[test]
+    fn unterminated_markup_is_not_formatted() {
+        assert_eq!(format_html("<div class=\"x>", false), None);
+        assert_eq!(format_html("<!-- open", false), None);
+    }
+}
@@ -1 +1,4 @@
 pub mod cli;
+pub mod html;
+pub mod openapi;
+pub mod output;
@@ -0,0 +1,201 @@
+use crate::domain::entities::{Body, Request};
+use crate::presentation::output::mask_credentials;
+use serde_json::Value;
+
+// Headers that OpenAPI describes elsewhere (Content-Type via the media type)
+// or that the transport adds
+const SKIPPED_HEADERS: [&str; 3] = ["host", "content-type", "content-length"];
+
+/// Formats the request as an OpenAPI `paths` entry in YAML
+///
+/// Query parameters and headers become parameters. A JSON body becomes a
+/// `requestBody` with a schema inferred from its values plus the body itself
+/// as the example. Credential headers and query parameters are kept, but
+/// their examples are masked, since exported specs tend to be shared.
+pub fn request_example(request: &Request) -> String {
+    let uri = &request.url.0;
+    let mut yaml = Yaml::default();
+
+    yaml.line(0, "paths:");
+    yaml.line(1, &format!("{}:", key(uri.path())));
+    yaml.line(2, &format!("{}:", request.method.as_str().to_lowercase()));
+
+    let query = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")));
+    let headers = request
+        .headers
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str()))
+        .map(|(name, value)| (name.as_str(), mask_credentials(name, value)));
+    let parameters: Vec<(&str, &str, String)> = query
+        .map(|(name, value)| ("query", name, mask_credentials(name, value)))
+        .chain(headers.map(|(name, value)| ("header", name, value)))
+        .collect();
+
+    if !parameters.is_empty() {
+        yaml.line(3, "parameters:");
+        for (location, name, value) in parameters {
+            yaml.line(4, &format!("- name: {}", key(name)));
+            yaml.line(5, &format!("in: {}", location));
+            yaml.line(5, "schema:");
+            yaml.line(6, "type: string");
+            yaml.line(5, &format!("example: {}", Value::from(value)));
+        }
+    }
+
+    if let Some(body) = &request.body {
+        let content_type = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_else(|| body.default_content_type());
+
+        yaml.line(3, "requestBody:");
+        yaml.line(4, "content:");
+        yaml.line(5, &format!("{}:", key(content_type)));
+        yaml.line(6, "schema:");
+
+        let json = match body {
+            Body::Json(json) => serde_json::from_str::<Value>(&json.0).ok(),
+            _ => None,
+        };
+        match json {
+            Some(json) => {
+                yaml.schema(7, &json);
+                // JSON is valid YAML, so the example can be written inline
+                yaml.line(6, &format!("example: {}", json));
+            }
+            None => {
+                yaml.line(7, "type: string");
+                yaml.line(7, "format: binary");
+            }
+        }
+    }
+
+    yaml.0.join("\n")
+}
+
+#[derive(Default)]
+struct Yaml(Vec<String>);
+
+impl Yaml {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.0.push(format!("{}{}", "  ".repeat(depth), text));
+    }
+
+    // Arrays take their item schema from the first element
+    fn schema(&mut self, depth: usize, json: &Value) {
+        match json {
+            Value::Null => self.line(depth, "nullable: true"),
+            Value::Bool(_) => self.line(depth, "type: boolean"),
+            Value::Number(number) if number.is_f64() => self.line(depth, "type: number"),
+            Value::Number(_) => self.line(depth, "type: integer"),
+            Value::String(_) => self.line(depth, "type: string"),
+            Value::Array(items) => {
+                self.line(depth, "type: array");
+                match items.first() {
+                    Some(first) => {
+                        self.line(depth, "items:");
+                        self.schema(depth + 1, first);
+                    }
+                    None => self.line(depth, "items: {}"),
+                }
+            }
+            Value::Object(map) => {
+                self.line(depth, "type: object");
+                if !map.is_empty() {
+                    self.line(depth, "properties:");
+                    for (name, value) in map {
+                        self.line(depth + 1, &format!("{}:", key(name)));
+                        self.schema(depth + 2, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Plain scalars are left bare; anything YAML could misread is quoted
+fn key(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+        && !text.starts_with('-')
+        && text.parse::<f64>().is_err()
+        && !matches!(
+            text.to_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off"
+        );
+    if plain {
+        text.to_string()
+    } else {
+        Value::from(text).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::entities::{Method, RequestBuilder};
+    use crate::domain::value_objects::{JsonBody, Url};
+
+    #[test]
+    fn credential_headers_are_masked() {
+        let request = RequestBuilder::new(Method::Get)
+            .base_url("https://api.test/users?api_key=k3y&page=2")
+            .basic_auth("alice", "s3cret")
+            .header("Cookie", "session=abc123")
+            .header("X-Api-Key", "topsecret")
+            .header("Accept", "application/json")
+            .build()
+            .unwrap();
+
+        let yaml = request_example(&request);
+
+        for secret in ["YWxpY2U6czNjcmV0", "abc123", "topsecret", "k3y"] {
+            assert!(!yaml.contains(secret), "{} leaked into:\n{}", secret, yaml);
+        }
+        assert!(yaml.contains("example: \"Basic ********\""));
+        assert!(yaml.contains("example: \"application/json\""));
+        assert!(yaml.contains("example: \"2\""));
+    }
+
+    #[test]
+    fn json_posts_become_a_request_body_with_a_schema_and_example() {
+        let request = Request {
+            method: Method::Post,
+            url: Url::new("http://api.test/users").unwrap(),
+            headers: Vec::new(),
+            body: Some(Body::Json(
+                JsonBody::new(r#"{"name":"a","tags":["x"]}"#).unwrap(),
+            )),
+        };
+
+        let expected = [
+            "paths:",
+            "  /users:",
+            "    post:",
+            "      requestBody:",
+            "        content:",
+            "          application/json:",
+            "            schema:",
+            "              type: object",
+            "              properties:",
+            "                name:",
+            "                  type: string",
+            "                tags:",
+            "                  type: array",
+            "                  items:",
+            "                    type: string",
+            r#"            example: {"name":"a","tags":["x"]}"#,
+        ];
+        assert_eq!(request_example(&request), expected.join("\n"));
+    }
+}
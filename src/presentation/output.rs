@@ -0,0 +1,848 @@
+use crate::application::comparison::Difference;
+use crate::domain::entities::{Body, ConnectionInfo, Request, Response, TlsInfo};
+use crate::infrastructure::http_client::resolved_headers;
+use crate::presentation::html::format_html;
+use anyhow::{Result, anyhow};
+use colored::{ColoredString, Colorize};
+use encoding_rs::Encoding;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::ser::PrettyFormatter;
+use std::io::{IsTerminal, Write};
+use std::str::FromStr;
+
+/// Default indentation width for pretty-printed JSON
+pub const DEFAULT_JSON_INDENT: usize = 2;
+
+/// How JSON response bodies are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Pretty-printed with the given number of spaces per level
+    Pretty(usize),
+    /// Everything on a single line
+    Compact,
+}
+
+/// Display settings for response bodies
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub json_style: JsonStyle,
+    /// Print JSON as `dotted.key = value` lines
+    pub flatten: bool,
+    /// Print at most this many bytes of a body, followed by a truncation notice
+    pub max_display_bytes: Option<usize>,
+    /// Write binary bodies to stdout even when it is a terminal
+    pub force_binary_stdout: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            json_style: JsonStyle::Pretty(DEFAULT_JSON_INDENT),
+            flatten: false,
+            max_display_bytes: None,
+            force_binary_stdout: false,
+        }
+    }
+}
+
+/// Which parts of the exchange to print, selected httpie-style with `--print`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrintSections {
+    /// `H`: request line and headers
+    pub request_headers: bool,
+    /// `B`: request body
+    pub request_body: bool,
+    /// `h`: response status and headers
+    pub response_headers: bool,
+    /// `b`: response body
+    pub response_body: bool,
+}
+
+impl FromStr for PrintSections {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sections = PrintSections::default();
+        for letter in s.chars() {
+            match letter {
+                'H' => sections.request_headers = true,
+                'B' => sections.request_body = true,
+                'h' => sections.response_headers = true,
+                'b' => sections.response_body = true,
+                other => {
+                    return Err(anyhow!(
+                        "Unsupported print section: '{}'. Use any of H, B, h, b",
+                        other
+                    ));
+                }
+            }
+        }
+        Ok(sections)
+    }
+}
+
+/// How a response body should be formatted for display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Json,
+    Xml,
+    Html,
+    Text,
+    Binary,
+}
+
+impl BodyKind {
+    /// Detects the body kind from the Content-Type header, sniffing the body when absent
+    pub fn detect(content_type: Option<&str>, body: &[u8]) -> Self {
+        match content_type {
+            Some(content_type) => {
+                Self::from_content_type(content_type).unwrap_or_else(|| Self::sniff(body))
+            }
+            None => Self::sniff(body),
+        }
+    }
+
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        if mime == "application/json" || mime.ends_with("+json") {
+            Some(BodyKind::Json)
+        } else if mime == "text/html" {
+            Some(BodyKind::Html)
+        } else if mime.ends_with("/xml") || mime.ends_with("+xml") {
+            Some(BodyKind::Xml)
+        } else if mime.starts_with("text/") {
+            Some(BodyKind::Text)
+        } else {
+            None
+        }
+    }
+
+    // Bytes that are not UTF-8 are binary; a charset in the Content-Type is
+    // the way to have them read as text
+    fn sniff(body: &[u8]) -> Self {
+        let Ok(body) = std::str::from_utf8(body) else {
+            return BodyKind::Binary;
+        };
+        let start = body.trim_start();
+        let lowered: String = start.chars().take(16).collect::<String>().to_lowercase();
+
+        if start.starts_with('{') || start.starts_with('[') {
+            BodyKind::Json
+        } else if lowered.starts_with("<!doctype html") || lowered.starts_with("<html") {
+            BodyKind::Html
+        } else if start.starts_with('<') {
+            BodyKind::Xml
+        } else if body
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+        {
+            BodyKind::Binary
+        } else {
+            BodyKind::Text
+        }
+    }
+}
+
+/// Prints a response body using the formatter for its detected kind
+pub fn print_response_body(response: &Response, options: &OutputOptions) -> Result<()> {
+    let kind = BodyKind::detect(response.header("content-type"), &response.body_bytes);
+
+    match kind {
+        BodyKind::Json | BodyKind::Text => print_json(&response.text(), options)?,
+        BodyKind::Binary => print_binary(&response.body_bytes, options)?,
+        BodyKind::Html => print_html(&response.text(), options),
+        BodyKind::Xml => print_limited(&response.text(), options.max_display_bytes, |text| {
+            text.white()
+        }),
+    }
+    Ok(())
+}
+
+// Raw binary would garble a terminal, but is what a pipe or redirect wants,
+// so the bytes are written exactly as received
+fn print_binary(bytes: &[u8], options: &OutputOptions) -> Result<()> {
+    if std::io::stdout().is_terminal() && !options.force_binary_stdout {
+        println!(
+            "{}",
+            format!(
+                "Binary response body ({} bytes); use --output to save it, or --force-binary-stdout",
+                bytes.len()
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| anyhow!("Failed to write the response body: {}", e))
+}
+
+/// Prints the request line and the headers that will be sent
+///
+/// Credential headers are masked with `mask_credentials` unless
+/// `show_secrets` is set.
+pub fn print_request_headers(
+    request: &Request,
+    header_order: &[String],
+    host_header: bool,
+    show_secrets: bool,
+) {
+    println!(
+        "{}",
+        format!("{} {}", request.method.as_str(), request.url.as_str()).magenta()
+    );
+    for (name, value) in resolved_headers(request, header_order, host_header) {
+        let value = if show_secrets {
+            value
+        } else {
+            mask_credentials(&name, &value)
+        };
+        println!("{}", format!("{}: {}", name, value).magenta());
+    }
+}
+
+/// Replaces the secret part of a credential header's value with `********`
+///
+/// Covers `Authorization` and `Proxy-Authorization` (keeping the scheme, so
+/// `Bearer ********`), `Cookie`, and API-key or token headers such as
+/// `X-Api-Key`. Other headers are returned unchanged.
+pub fn mask_credentials(name: &str, value: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        "authorization" | "proxy-authorization" => match value.split_once(' ') {
+            Some((scheme, _)) => format!("{} ********", scheme),
+            None => "********".to_string(),
+        },
+        "cookie" => "********".to_string(),
+        _ if ["api-key", "api_key", "apikey", "token", "secret"]
+            .iter()
+            .any(|word| name.contains(word)) =>
+        {
+            "********".to_string()
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Prints the request body, if any, truncated to `max_bytes`
+pub fn print_request_body(request: &Request, max_bytes: Option<usize>) {
+    match &request.body {
+        Some(Body::File(file)) => println!(
+            "{}",
+            format!("<streamed from {}>", file.path.display()).magenta()
+        ),
+        Some(body) => print_limited(
+            &String::from_utf8_lossy(body.as_bytes().unwrap_or_default()),
+            max_bytes,
+            |text| text.magenta(),
+        ),
+        None => {}
+    }
+}
+
+/// Prints the result of a `--connect-only` check
+pub fn print_connection_info(info: &ConnectionInfo) {
+    println!(
+        "{}",
+        format!(
+            "Connected to {} in {}ms",
+            info.peer,
+            info.elapsed.as_millis()
+        )
+        .green()
+    );
+    if let Some(tls) = &info.tls {
+        for line in tls_lines(tls) {
+            println!("{}", line.cyan());
+        }
+    }
+}
+
+/// Describes the TLS session a response arrived over, on stderr so it stays
+/// out of piped output
+pub fn print_tls_info(tls: &TlsInfo) {
+    for line in tls_lines(tls) {
+        eprintln!("{}", line.cyan());
+    }
+}
+
+fn tls_lines(tls: &TlsInfo) -> [String; 4] {
+    [
+        format!("TLS handshake complete (server name {})", tls.server_name),
+        format!("Protocol: {}", tls.protocol.as_deref().unwrap_or("unknown")),
+        format!("Cipher: {}", tls.cipher.as_deref().unwrap_or("unknown")),
+        format!("ALPN: {}", tls.alpn.as_deref().unwrap_or("none negotiated")),
+    ]
+}
+
+/// Prints the differences between a primary and a secondary response
+pub fn print_differences(differences: &[Difference]) {
+    if differences.is_empty() {
+        println!("{}", "Responses match".green());
+        return;
+    }
+
+    for difference in differences {
+        let line = match difference {
+            Difference::Status { primary, secondary } => {
+                format!("Status: {} != {}", primary, secondary)
+            }
+            Difference::Header {
+                name,
+                primary,
+                secondary,
+            } => format!(
+                "Header {}: {} != {}",
+                name,
+                primary.as_deref().unwrap_or("<missing>"),
+                secondary.as_deref().unwrap_or("<missing>")
+            ),
+            Difference::Body => "Body differs".to_string(),
+        };
+        println!("{}", line.red());
+    }
+}
+
+/// Prints only the named response headers
+///
+/// A single name prints just the value(s) for easy capture in scripts; several
+/// names print `Name: value` lines.
+///
+/// # Arguments
+/// * `response` - The response to read headers from
+/// * `names` - Header names to print, matched case-insensitively
+/// * `required` - Whether a missing header is an error rather than printing nothing
+pub fn print_selected_headers(response: &Response, names: &[String], required: bool) -> Result<()> {
+    for line in selected_header_lines(response, names, required)? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn selected_header_lines(
+    response: &Response,
+    names: &[String],
+    required: bool,
+) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for name in names {
+        let values: Vec<&str> = response
+            .headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect();
+
+        if values.is_empty() && required {
+            return Err(anyhow!("Response has no '{}' header", name));
+        }
+
+        for value in values {
+            if names.len() == 1 {
+                lines.push(value.to_string());
+            } else {
+                lines.push(format!("{}: {}", name, value));
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// Prints line and/or byte counts of the response body
+///
+/// A single count prints just the number; both print labelled lines.
+pub fn print_body_counts(response: &Response, lines: bool, bytes: bool) {
+    for line in body_count_lines(response, lines, bytes) {
+        println!("{}", line);
+    }
+}
+
+fn body_count_lines(response: &Response, lines: bool, bytes: bool) -> Vec<String> {
+    let line_count = response.text().lines().count();
+    let byte_count = response.body_bytes.len();
+
+    match (lines, bytes) {
+        (true, true) => vec![
+            format!("lines: {}", line_count),
+            format!("bytes: {}", byte_count),
+        ],
+        (true, false) => vec![line_count.to_string()],
+        (false, true) => vec![byte_count.to_string()],
+        (false, false) => Vec::new(),
+    }
+}
+
+/// Looks up a charset by its WHATWG label, e.g. `utf-8`, `latin1` or `shift_jis`
+pub fn output_encoding(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| anyhow!("Unknown output charset '{}'", label))
+}
+
+/// Transcodes the body into the given charset for writing to a file
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - The encoded bytes
+/// * `Err(anyhow::Error)` - If the charset cannot be written or a character has no mapping in it
+pub fn encode_body(body: &str, encoding: &'static Encoding) -> Result<Vec<u8>> {
+    let (bytes, used, unmappable) = encoding.encode(body);
+    if used != encoding {
+        return Err(anyhow!(
+            "Writing {} output is not supported",
+            encoding.name()
+        ));
+    }
+    if unmappable {
+        return Err(anyhow!(
+            "Response body has characters that cannot be encoded as {}",
+            encoding.name()
+        ));
+    }
+    Ok(bytes.into_owned())
+}
+
+/// Prints the response as one self-contained JSON object on a single line
+///
+/// The whole line is written under one stdout lock so records never interleave.
+pub fn print_json_line(url: &str, response: &Response) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", json_line(url, response))
+        .map_err(|e| anyhow!("Failed to write output: {}", e))
+}
+
+// serde_json escapes newlines inside strings, so the record never spans lines
+fn json_line(url: &str, response: &Response) -> String {
+    serde_json::json!({
+        "url": url,
+        "status": response.status.as_u16(),
+        "timing": {
+            "connect_ms": response.timing.connect.as_millis() as u64,
+            "total_ms": response.timing.total.as_millis() as u64,
+        },
+        "body": response.text(),
+    })
+    .to_string()
+}
+
+// Pretty-prints JSON, falling back to plain text when the body does not parse
+fn print_json(body: &str, options: &OutputOptions) -> Result<()> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(json) if options.flatten => print_limited(
+            &flatten_json(&json).join("\n"),
+            options.max_display_bytes,
+            |text| text.green(),
+        ),
+        Ok(json) => print_limited(
+            &format_json(&json, options.json_style)?,
+            options.max_display_bytes,
+            |text| text.green(),
+        ),
+        Err(_) => print_limited(body, options.max_display_bytes, |text| text.white()),
+    }
+    Ok(())
+}
+
+// Markup that cannot be tokenized is printed as received. Highlighting is
+// left out whenever a display limit is set, so a cut never lands inside a colour code
+fn print_html(body: &str, options: &OutputOptions) {
+    let highlight = options.max_display_bytes.is_none();
+    match format_html(body, highlight) {
+        Some(html) if highlight => println!("{}", html),
+        Some(html) => print_limited(&html, options.max_display_bytes, |text| text.white()),
+        None => print_limited(body, options.max_display_bytes, |text| text.white()),
+    }
+}
+
+fn print_limited(text: &str, max_bytes: Option<usize>, paint: impl Fn(&str) -> ColoredString) {
+    let (shown, notice) = limit_text(text, max_bytes);
+    println!("{}", paint(shown));
+    if let Some(notice) = notice {
+        println!("{}", notice.yellow());
+    }
+}
+
+// Cuts the text at the last character boundary within the limit and reports
+// how much was left out
+fn limit_text(text: &str, max_bytes: Option<usize>) -> (&str, Option<String>) {
+    match max_bytes {
+        Some(limit) if text.len() > limit => {
+            let end = text.floor_char_boundary(limit);
+            (
+                &text[..end],
+                Some(format!("... (truncated, {} more bytes)", text.len() - end)),
+            )
+        }
+        _ => (text, None),
+    }
+}
+
+/// Flattens JSON into `dotted.key = value` lines, using numeric indices for arrays
+pub fn flatten_json(json: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    flatten_into(json, String::new(), &mut lines);
+    lines
+}
+
+fn flatten_into(json: &Value, path: String, lines: &mut Vec<String>) {
+    let child_path = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match json {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                flatten_into(value, child_path(key), lines);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, value) in items.iter().enumerate() {
+                flatten_into(value, child_path(&index.to_string()), lines);
+            }
+        }
+        leaf if path.is_empty() => lines.push(leaf.to_string()),
+        leaf => lines.push(format!("{} = {}", path, leaf)),
+    }
+}
+
+fn format_json(json: &Value, style: JsonStyle) -> Result<String> {
+    let indent = match style {
+        JsonStyle::Compact => {
+            return serde_json::to_string(json)
+                .map_err(|e| anyhow!("Failed to format JSON: {}", e));
+        }
+        JsonStyle::Pretty(width) => " ".repeat(width),
+    };
+
+    let mut buf = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut buf,
+        PrettyFormatter::with_indent(indent.as_bytes()),
+    );
+    json.serialize(&mut serializer)
+        .map_err(|e| anyhow!("Failed to format JSON: {}", e))?;
+    String::from_utf8(buf).map_err(|e| anyhow!("Failed to format JSON: {}", e))
+}
+
+/// Prints the trailers that followed a chunked response body, if there were any
+pub fn print_trailers(trailers: &[(String, String)]) {
+    for line in trailer_lines(trailers) {
+        println!("{}", line.cyan());
+    }
+}
+
+fn trailer_lines(trailers: &[(String, String)]) -> Vec<String> {
+    if trailers.is_empty() {
+        return Vec::new();
+    }
+    std::iter::once("Trailers:".to_string())
+        .chain(
+            trailers
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value)),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_credentials_keeps_the_scheme() {
+        assert_eq!(
+            mask_credentials("Authorization", "Bearer abc"),
+            "Bearer ********"
+        );
+        assert_eq!(
+            mask_credentials("proxy-authorization", "opaque"),
+            "********"
+        );
+    }
+
+    #[test]
+    fn mask_credentials_covers_cookies_and_api_keys() {
+        assert_eq!(mask_credentials("Cookie", "session=abc"), "********");
+        assert_eq!(mask_credentials("X-Api-Key", "k"), "********");
+        assert_eq!(mask_credentials("X-Auth-Token", "t"), "********");
+        assert_eq!(mask_credentials("Accept", "text/html"), "text/html");
+    }
+
+    #[test]
+    fn body_kind_prefers_the_content_type() {
+        let detect = |content_type, body: &str| BodyKind::detect(content_type, body.as_bytes());
+        assert_eq!(
+            detect(Some("application/problem+json"), "x"),
+            BodyKind::Json
+        );
+        assert_eq!(
+            detect(Some("Text/HTML; charset=utf-8"), "{}"),
+            BodyKind::Html
+        );
+        assert_eq!(detect(Some("application/atom+xml"), "{}"), BodyKind::Xml);
+        assert_eq!(detect(Some("text/csv"), "{}"), BodyKind::Text);
+    }
+
+    #[test]
+    fn body_kind_is_sniffed_without_a_known_content_type() {
+        let detect = |content_type, body: &str| BodyKind::detect(content_type, body.as_bytes());
+        assert_eq!(detect(None, "  [1, 2]"), BodyKind::Json);
+        assert_eq!(detect(None, "<!DOCTYPE html><p>"), BodyKind::Html);
+        assert_eq!(
+            detect(Some("application/octet-stream"), "<note/>"),
+            BodyKind::Xml
+        );
+        assert_eq!(detect(None, "plain\ttext\n"), BodyKind::Text);
+        assert_eq!(detect(None, "nul\u{0}byte"), BodyKind::Binary);
+    }
+
+    #[test]
+    fn bytes_that_are_not_utf8_are_binary() {
+        assert_eq!(
+            BodyKind::detect(None, b"\x89PNG\r\n\x1a\n\xff"),
+            BodyKind::Binary
+        );
+        assert_eq!(
+            BodyKind::detect(Some("image/png"), b"\xff\xd8\xff"),
+            BodyKind::Binary
+        );
+        assert_eq!(
+            BodyKind::detect(Some("text/plain; charset=latin1"), b"caf\xe9"),
+            BodyKind::Text
+        );
+    }
+
+    #[test]
+    fn trailers_are_listed_under_a_heading_only_when_present() {
+        assert!(trailer_lines(&[]).is_empty());
+        let trailers = [
+            ("x-checksum".to_string(), "abc".to_string()),
+            ("x-count".to_string(), "2".to_string()),
+        ];
+        assert_eq!(
+            trailer_lines(&trailers),
+            ["Trailers:", "x-checksum: abc", "x-count: 2"]
+        );
+    }
+
+    #[test]
+    fn print_sections_parse_httpie_letters() {
+        let sections: PrintSections = "Hb".parse().unwrap();
+        assert_eq!(
+            sections,
+            PrintSections {
+                request_headers: true,
+                response_body: true,
+                ..Default::default()
+            }
+        );
+        let error = "hx".parse::<PrintSections>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unsupported print section: 'x'. Use any of H, B, h, b"
+        );
+    }
+
+    fn response_with(headers: &[(&str, &str)], body: &str) -> Response {
+        Response {
+            status: hyper::StatusCode::OK,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body_bytes: body.as_bytes().to_vec(),
+            trailers: Vec::new(),
+            timing: Default::default(),
+            tls: None,
+        }
+    }
+
+    #[test]
+    fn one_selected_header_prints_bare_values_and_several_print_names() {
+        let response = response_with(
+            &[
+                ("Content-Type", "text/plain"),
+                ("Set-Cookie", "a=1"),
+                ("set-cookie", "b=2"),
+            ],
+            "",
+        );
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            selected_header_lines(&response, &names(&["content-type"]), false).unwrap(),
+            ["text/plain"]
+        );
+        assert_eq!(
+            selected_header_lines(&response, &names(&["Set-Cookie"]), false).unwrap(),
+            ["a=1", "b=2"]
+        );
+        assert_eq!(
+            selected_header_lines(
+                &response,
+                &names(&["Content-Type", "Set-Cookie", "ETag"]),
+                false
+            )
+            .unwrap(),
+            [
+                "Content-Type: text/plain",
+                "Set-Cookie: a=1",
+                "Set-Cookie: b=2"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_selected_headers_are_errors_only_when_required() {
+        let response = response_with(&[("Content-Type", "text/plain")], "");
+        let names = ["ETag".to_string()];
+
+        assert!(
+            selected_header_lines(&response, &names, false)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            selected_header_lines(&response, &names, true)
+                .unwrap_err()
+                .to_string(),
+            "Response has no 'ETag' header"
+        );
+    }
+
+    #[test]
+    fn json_is_indented_by_the_requested_width() {
+        let json = serde_json::json!({"a": [1]});
+        assert_eq!(
+            format_json(&json, JsonStyle::Pretty(4)).unwrap(),
+            "{\n    \"a\": [\n        1\n    ]\n}"
+        );
+        assert_eq!(
+            format_json(&json, JsonStyle::Pretty(0)).unwrap(),
+            "{\n\"a\": [\n1\n]\n}"
+        );
+        assert_eq!(
+            format_json(&json, JsonStyle::Compact).unwrap(),
+            r#"{"a":[1]}"#
+        );
+    }
+
+    #[test]
+    fn flatten_json_uses_dotted_keys_and_indices() {
+        let json =
+            serde_json::json!({"user": {"name": "a", "tags": ["x", 2]}, "empty": {}, "none": []});
+        assert_eq!(
+            flatten_json(&json),
+            vec![
+                "empty = {}",
+                "none = []",
+                "user.name = \"a\"",
+                "user.tags.0 = \"x\"",
+                "user.tags.1 = 2",
+            ]
+        );
+        assert_eq!(flatten_json(&serde_json::json!(5)), vec!["5"]);
+    }
+
+    #[test]
+    fn bodies_are_encoded_in_the_output_charset() {
+        let latin1 = output_encoding(" Latin1 ").unwrap();
+        assert_eq!(encode_body("café", latin1).unwrap(), b"caf\xe9");
+        assert!(output_encoding("klingon").is_err());
+    }
+
+    #[test]
+    fn unwritable_charsets_and_unmappable_characters_are_errors() {
+        let latin1 = output_encoding("latin1").unwrap();
+        assert_eq!(
+            encode_body("snow ☃", latin1).unwrap_err().to_string(),
+            "Response body has characters that cannot be encoded as windows-1252"
+        );
+        let utf16 = output_encoding("utf-16le").unwrap();
+        assert_eq!(
+            encode_body("x", utf16).unwrap_err().to_string(),
+            "Writing UTF-16LE output is not supported"
+        );
+    }
+
+    #[test]
+    fn body_counts_are_bare_alone_and_labelled_together() {
+        let response = response_with(&[], "first\nsecond\nthird\n");
+
+        assert_eq!(body_count_lines(&response, true, false), ["3"]);
+        assert_eq!(body_count_lines(&response, false, true), ["19"]);
+        assert_eq!(
+            body_count_lines(&response, true, true),
+            ["lines: 3", "bytes: 19"]
+        );
+        assert!(body_count_lines(&response, false, false).is_empty());
+    }
+
+    #[test]
+    fn json_lines_are_single_line_json_records() {
+        let mut first = response_with(&[], "line one\nline \"two\"\n");
+        first.timing.connect = std::time::Duration::from_millis(3);
+        first.timing.total = std::time::Duration::from_millis(12);
+        let second = response_with(&[], "{}");
+
+        let output = [
+            json_line("http://a.test/1", &first),
+            json_line("http://a.test/2", &second),
+        ]
+        .join("\n");
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            records,
+            [
+                serde_json::json!({
+                    "url": "http://a.test/1",
+                    "status": 200,
+                    "timing": {"connect_ms": 3, "total_ms": 12},
+                    "body": "line one\nline \"two\"\n",
+                }),
+                serde_json::json!({
+                    "url": "http://a.test/2",
+                    "status": 200,
+                    "timing": {"connect_ms": 0, "total_ms": 0},
+                    "body": "{}",
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_text_is_cut_with_a_notice_of_what_was_left_out() {
+        assert_eq!(
+            limit_text("hello world", Some(5)),
+            ("hello", Some("... (truncated, 6 more bytes)".to_string()))
+        );
+        assert_eq!(limit_text("hello", Some(5)), ("hello", None));
+        assert_eq!(limit_text("hello world", None), ("hello world", None));
+        // "é" is two bytes, so a limit inside it cuts before it
+        assert_eq!(
+            limit_text("café au lait", Some(4)),
+            ("caf", Some("... (truncated, 10 more bytes)".to_string()))
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Exit status and output of the hurl binary for modes that need no server
+
+use std::process::{Command, Output};
+
+fn hurl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hurl"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn validate_only_exits_zero_for_a_valid_request_and_one_for_an_invalid_url() {
+    // .invalid never resolves, so success shows nothing was looked up or sent
+    let valid = hurl(&["--validate-only", "http://no-such-host.invalid/users"]);
+    assert!(valid.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&valid.stdout),
+        "Valid: GET http://no-such-host.invalid/users\n"
+    );
+
+    let invalid = hurl(&["--validate-only", "not a url"]);
+    assert_eq!(invalid.status.code(), Some(1));
+    assert!(invalid.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    assert!(stderr.starts_with("Invalid URL"), "{stderr}");
+}
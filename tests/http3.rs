@@ -0,0 +1,155 @@
+//! End-to-end checks for the experimental `--http3` transport against a local
+//! HTTP/3 server, run with `cargo test --features http3`
+#![cfg(feature = "http3")]
+
+use hurl::application::services::HttpClient;
+use hurl::domain::entities::{Body, Method, Request};
+use hurl::domain::value_objects::{JsonBody, Url};
+use hurl::infrastructure::http_client::{ClientOptions, HyperHttpClient};
+use hyper::body::{Buf, Bytes};
+use quinn::crypto::rustls::QuicServerConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::sync::{Arc, Once};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+// The client verifies against the platform trust store, which honours SSL_CERT_FILE
+fn trust_test_ca() {
+    static TRUST: Once = Once::new();
+    TRUST.call_once(|| {
+        // SAFETY: runs once, before any test reads the environment
+        unsafe { std::env::set_var("SSL_CERT_FILE", format!("{}/ca.pem", FIXTURES)) };
+    });
+}
+
+// A QUIC endpoint on 127.0.0.1 with a certificate signed by the test CA,
+// offering only the given ALPN protocol
+fn server(alpn: &[u8]) -> quinn::Endpoint {
+    let certs = CertificateDer::pem_file_iter(format!("{}/localhost.pem", FIXTURES))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(format!("{}/localhost.key", FIXTURES)).unwrap();
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut tls = rustls::ServerConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+    tls.alpn_protocols = vec![alpn.to_vec()];
+    let config =
+        quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls).unwrap()));
+    quinn::Endpoint::server(config, "127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+// Answers one request with a 201 describing what arrived, then waits for the client to close
+async fn serve_one(endpoint: quinn::Endpoint) {
+    let connection = endpoint.accept().await.unwrap().await.unwrap();
+    let mut h3 = h3::server::Connection::new(h3_quinn::Connection::new(connection))
+        .await
+        .unwrap();
+    let resolver = h3.accept().await.unwrap().unwrap();
+    let (request, mut stream) = resolver.resolve_request().await.unwrap();
+
+    let mut body = Vec::new();
+    while let Some(mut chunk) = stream.recv_data().await.unwrap() {
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+    let seen = format!(
+        "{} {} host={} content-type={} body={}",
+        request.method(),
+        request.uri(),
+        request.headers().contains_key("host"),
+        request.headers()["content-type"].to_str().unwrap(),
+        String::from_utf8_lossy(&body)
+    );
+
+    let response = hyper::Response::builder()
+        .status(201)
+        .header("x-server", "h3-test")
+        .body(())
+        .unwrap();
+    stream.send_response(response).await.unwrap();
+    stream.send_data(Bytes::from(seen)).await.unwrap();
+    stream.finish().await.unwrap();
+    let _ = h3.accept().await;
+}
+
+fn client() -> HyperHttpClient {
+    HyperHttpClient::new(ClientOptions {
+        http3: true,
+        ..ClientOptions::default()
+    })
+}
+
+fn request(method: Method, url: &str, body: Option<Body>) -> Request {
+    Request {
+        method,
+        url: Url::new(url).unwrap(),
+        headers: Vec::new(),
+        body,
+    }
+}
+
+#[tokio::test]
+async fn requests_are_sent_over_http3() {
+    trust_test_ca();
+    let endpoint = server(b"h3");
+    let addr = endpoint.local_addr().unwrap();
+    let server = tokio::spawn(serve_one(endpoint));
+
+    let authority = format!("127.0.0.1:{}", addr.port());
+    let request = request(
+        Method::Post,
+        &format!("https://{}/items?x=1", authority),
+        Some(Body::Json(JsonBody::new(r#"{"a":1}"#).unwrap())),
+    );
+    let response = client().send(request).await.unwrap();
+
+    assert_eq!(response.status, 201);
+    assert!(
+        response
+            .headers
+            .contains(&("x-server".to_string(), "h3-test".to_string()))
+    );
+    assert_eq!(
+        String::from_utf8(response.body_bytes).unwrap(),
+        format!(
+            "POST https://{}/items?x=1 host=false content-type=application/json body={{\"a\":1}}",
+            authority
+        )
+    );
+    assert_eq!(response.tls.unwrap().alpn.as_deref(), Some("h3"));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn servers_without_http3_get_a_clear_error() {
+    trust_test_ca();
+    let endpoint = server(b"h2");
+    let port = endpoint.local_addr().unwrap().port();
+    tokio::spawn(async move { endpoint.accept().await.unwrap().await });
+
+    let request = request(Method::Get, &format!("https://127.0.0.1:{}/", port), None);
+    let error = client().send(request).await.unwrap_err().to_string();
+
+    assert!(
+        error.starts_with(&format!(
+            "127.0.0.1:{} does not support HTTP/3 (try again without --http3)",
+            port
+        )),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn http3_needs_an_https_url() {
+    let request = request(Method::Get, "http://127.0.0.1:1/", None);
+    let error = client().send(request).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "--http3 needs an https:// URL; HTTP/3 always runs over TLS"
+    );
+}
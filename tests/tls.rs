@@ -0,0 +1,111 @@
+//! End-to-end checks of what the binary reports about TLS sessions, against
+//! a local native-tls server using the certificates in `tests/fixtures`
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+// Accepts connections on 127.0.0.1 with the named fixture certificate and
+// answers each completed handshake with an empty 200. native-tls servers
+// speak at most TLS 1.2; the cap is explicit so the session is predictable.
+async fn server(certificate: &str) -> u16 {
+    let read = |extension| std::fs::read(format!("{}/{}.{}", FIXTURES, certificate, extension));
+    let identity =
+        native_tls::Identity::from_pkcs8(&read("pem").unwrap(), &read("key").unwrap()).unwrap();
+    let acceptor = native_tls::TlsAcceptor::builder(identity)
+        .max_protocol_version(Some(native_tls::Protocol::Tlsv12))
+        .build()
+        .unwrap();
+    let acceptor = tokio_native_tls::TlsAcceptor::from(acceptor);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            // Handshakes the client rejects are expected, so their errors are ignored
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut head = Vec::new();
+                while !head.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0u8; 1];
+                    if stream.read_exact(&mut byte).await.is_err() {
+                        return;
+                    }
+                    head.push(byte[0]);
+                }
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                    .await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    port
+}
+
+// Runs the hurl binary, trusting only the test CA, and returns its exit
+// status and stderr
+async fn hurl(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_hurl"))
+        .args(args)
+        .env("SSL_CERT_FILE", format!("{}/ca.pem", FIXTURES))
+        .env("NO_COLOR", "1")
+        .output()
+        .await
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[tokio::test]
+async fn show_cert_on_error_prints_a_self_signed_certificate() {
+    let port = server("self-signed").await;
+    let url = format!("https://localhost:{}/", port);
+
+    let (success, stderr) = hurl(&["--show-cert-on-error", &url]).await;
+
+    assert!(!success);
+    assert!(stderr.contains("Server certificate:\n"), "{stderr}");
+    assert!(stderr.contains("  subject: CN=Hurl Self-Signed\n"), "{stderr}");
+    assert!(stderr.contains("  issuer: CN=Hurl Self-Signed\n"), "{stderr}");
+    assert!(stderr.contains("  valid until: "), "{stderr}");
+    assert!(
+        stderr.contains("  alternative names: DNSName(localhost), IPAddress(127.0.0.1)"),
+        "{stderr}"
+    );
+
+    let (_, stderr) = hurl(&[&url]).await;
+    assert!(!stderr.contains("Server certificate:"), "{stderr}");
+}
+
+#[tokio::test]
+async fn verbose_output_shows_the_negotiated_tls_session() {
+    let port = server("localhost").await;
+    let url = format!("https://localhost:{}/", port);
+
+    let (success, stderr) = hurl(&["--verbose", &url]).await;
+
+    assert!(success, "{stderr}");
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert!(
+        lines.contains(&"TLS handshake complete (server name localhost)"),
+        "{stderr}"
+    );
+    assert!(lines.contains(&"Protocol: TLSv1.2"), "{stderr}");
+    // The fixture key is ECDSA, so only ECDHE-ECDSA suites can be chosen
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("Cipher: TLS_ECDHE_ECDSA_WITH_")),
+        "{stderr}"
+    );
+    assert!(lines.contains(&"ALPN: none negotiated"), "{stderr}");
+}